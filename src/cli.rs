@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use clap::builder::BoolishValueParser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 #[derive(Parser)]
 #[command(author = "domahet", version, about = "A ferry for your files", long_about = None)]
@@ -6,62 +8,322 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    /// Suppress all output to stdout. Only errors will be printed to stderr.
-    #[arg(long, default_value_t = false, conflicts_with = "verbose")]
+    /// Suppress all output to stdout. Only errors will be printed to stderr. Cannot be combined with --verbose.
+    #[arg(long, env = "FERRY_SILENT", value_parser = BoolishValueParser::new(), default_value_t = false)]
     pub silent: bool,
 
     /// Print all available information, including file names during selection.
-    #[arg(long, default_value_t = false, conflicts_with = "silent")]
+    #[arg(long, env = "FERRY_VERBOSE", value_parser = BoolishValueParser::new(), default_value_t = false)]
     pub verbose: bool,
+
+    /// Operate on a named selection instead of the default one (e.g. --hold photos).
+    #[arg(long, global = true, env = "FERRY_HOLD", value_name = "NAME")]
+    pub hold: Option<String>,
+
+    /// Output format for list, select, copy, move and drop. Machine-readable formats print one
+    /// record per path on stdout and send the usual messages to stderr.
+    #[arg(long, global = true, env = "FERRY_FORMAT", value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable messages.
+    Text,
+    /// A single JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Jsonl,
+    /// NUL-terminated paths, for `xargs -0`.
+    Null,
+}
+
+/// What to do when an item's destination already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Stop with an error (default).
+    #[default]
+    Fail,
+    /// Leave the existing file alone and keep the item selected.
+    Skip,
+    /// Replace the existing file.
+    Overwrite,
+}
+
+/// How a new selection combines with the stored one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionMode {
+    /// Add to the stored selection (default).
+    #[default]
+    Add,
+    /// Replace the stored selection.
+    Replace,
+}
+
+/// Conflict handling shared by copy, move and drop.
+#[derive(Args)]
+pub struct ConflictArgs {
+    /// Overwrite existing files; short for --on-conflict overwrite.
+    #[arg(short = 'f', long, conflicts_with = "on_conflict")]
+    pub force: bool,
+
+    /// What to do when a destination already exists (default from the config file, else fail).
+    #[arg(long, value_enum, value_name = "STRATEGY")]
+    pub on_conflict: Option<ConflictStrategy>,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Select files for copying or moving
-    Select {
-        /// Paths to items to select directly (shell-globbed).
-        /// These are positional arguments. They are ignored if --regex or --interactive is used.
-        items: Vec<String>, 
-
-        /// Select items by regular expression (e.g., "^doc_\\d{3}\\.pdf$")
-        /// This activates regex search mode.
-        #[arg(long, conflicts_with = "interactive")]
-        regex: Option<String>, 
-
-        /// Launch an interactive Text User Interface (TUI) for selection.
-        /// This activates TUI mode.
-        #[arg(short = 'i', long)]
-        interactive: bool, 
-
-        /// Specify the starting directory for the regex search or interactive TUI.
-        /// Defaults to the current working directory.
-        #[arg(short = 'P', long)]
-        path: Option<String>, 
-
-        /// Perform a dry run: show what would be selected without saving to the selection file.
-        #[arg(long)]
-        dry_run: bool, 
-    },
+    Select(Box<SelectArgs>),
     /// Copy previously selected items to the current directory
     Copy {
-        /// Overwrite existing files without prompting.
-        #[arg(short = 'f', long)]
-        force: bool, 
+        #[command(flatten)]
+        conflict: ConflictArgs,
+
+        /// Keep the selection after copying instead of clearing it.
+        #[arg(short = 'k', long)]
+        keep: bool,
     },
     /// Move previously selected items to the current directory
     Move {
-        /// Overwrite existing files without prompting.
-        #[arg(short = 'f', long)]
-        force: bool, 
+        #[command(flatten)]
+        conflict: ConflictArgs,
+    },
+    /// Copy or move previously selected items into the current directory, or into a
+    /// directory picked in a browser with -i
+    Drop {
+        /// Browse for the destination, review what will happen to each item and run it with progress.
+        #[arg(short = 'i', long)]
+        interactive: bool,
+
+        /// Directory to start browsing from with -i (default: the current directory).
+        #[arg(short = 'P', long, requires = "interactive")]
+        path: Option<String>,
+
+        /// Move the items instead of copying them (can also be switched in the browser).
+        #[arg(short = 'm', long = "move")]
+        move_items: bool,
+
+        #[command(flatten)]
+        conflict: ConflictArgs,
+
+        /// Keep the selection after copying instead of clearing the dropped items.
+        #[arg(short = 'k', long)]
+        keep: bool,
     },
     /// List currently selected files
     List {
         /// Display paths as absolute paths (default).
-        #[arg(long, default_value_t = false, conflicts_with = "relative")]
+        #[arg(long, default_value_t = false)]
         absolute: bool,
 
-        /// Display paths relative to the current working directory.
-        #[arg(long, default_value_t = false, conflicts_with = "absolute")]
+        /// Display paths relative to the current working directory. Cannot be combined with --absolute or -i.
+        #[arg(long, env = "FERRY_LIST_RELATIVE", value_parser = BoolishValueParser::new(), default_value_t = false)]
         relative: bool,
+
+        /// Review the selection grouped by directory: remove and reorder entries, then save or copy/move them.
+        #[arg(short = 'i', long, conflicts_with = "absolute")]
+        interactive: bool,
     },
-}
\ No newline at end of file
+    /// Show, edit or locate the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the settings in effect, merged from the config files, FERRY_* variables and defaults
+    Show {
+        /// Print one key per line with where its value comes from.
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Open the config file in $VISUAL or $EDITOR, creating it if needed
+    Edit {
+        /// Edit the project's .ferry.toml instead of the user config file.
+        #[arg(long)]
+        project: bool,
+    },
+    /// Print the path of the config file
+    Path {
+        /// Print the path of the project's .ferry.toml instead.
+        #[arg(long)]
+        project: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct SelectArgs {
+    /// Paths to items to select directly (shell-globbed).
+    /// These are positional arguments. They are ignored if --regex, --glob or --interactive is used.
+    pub items: Vec<String>, 
+
+    /// Select items by regular expression (e.g., "^doc_\\d{3}\\.pdf$")
+    /// This activates regex search mode.
+    #[arg(long, conflicts_with_all = ["interactive", "glob"])]
+    pub regex: Option<String>, 
+
+    /// What the --regex pattern is matched against.
+    #[arg(long, env = "FERRY_MATCH_ON", value_enum, default_value_t = MatchTarget::Name, requires = "regex")]
+    pub match_on: MatchTarget,
+
+    /// Select items by glob pattern, with `**` matching any number of directories (e.g., "**/*.rs").
+    /// Patterns containing a '/' match the path relative to --path, others match the file name.
+    /// This activates glob search mode.
+    #[arg(long, conflicts_with = "interactive")]
+    pub glob: Option<String>,
+
+    /// Read paths to select from standard input, one per line (e.g. `fd -e rs | ferry select --stdin`).
+    #[arg(long, conflicts_with_all = ["items", "regex", "glob", "interactive", "path"])]
+    pub stdin: bool,
+
+    /// With --stdin, paths are separated by NUL characters instead of newlines (as printed by `find -print0`).
+    #[arg(short = '0', long = "null", env = "FERRY_NULL", value_parser = BoolishValueParser::new(), requires = "stdin")]
+    pub null_delimited: bool,
+
+    /// Launch an interactive Text User Interface (TUI) for selection.
+    /// This activates TUI mode.
+    #[arg(short = 'i', long)]
+    pub interactive: bool, 
+
+    /// Specify the starting directory for the regex or glob search or interactive TUI.
+    /// Defaults to the current working directory.
+    #[arg(short = 'P', long)]
+    pub path: Option<String>, 
+
+    /// Perform a dry run: show what would be selected without saving to the selection file.
+    #[arg(long, env = "FERRY_DRY_RUN", value_parser = BoolishValueParser::new())]
+    pub dry_run: bool, 
+
+    #[command(flatten)]
+    pub traversal: TraversalArgs,
+
+    #[command(flatten)]
+    pub combine: CombineArgs,
+}
+
+/// The part of a path a --regex pattern is matched against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MatchTarget {
+    /// The file name alone, e.g. "report.pdf".
+    Name,
+    /// The path relative to --path, e.g. "docs/report.pdf".
+    Relpath,
+    /// The absolute path, e.g. "/home/me/docs/report.pdf".
+    Abspath,
+}
+
+/// Options controlling which entries a regex or glob search or the TUI visits.
+#[derive(Args, Clone)]
+pub struct TraversalArgs {
+    /// Only keep entries matching this glob pattern. May be given multiple times.
+    #[arg(long, env = "FERRY_INCLUDE", value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip entries (and the contents of directories) matching this glob pattern. May be given multiple times.
+    /// Replaces the `exclude` patterns of the config file.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Match regex and glob patterns case-insensitively.
+    #[arg(short = 'I', long, env = "FERRY_IGNORE_CASE", value_parser = BoolishValueParser::new())]
+    pub ignore_case: bool,
+
+    /// Do not skip entries listed in .gitignore, .ignore, .ferryignore or the global git excludes.
+    #[arg(long, overrides_with = "ignore")]
+    pub no_ignore: bool,
+
+    /// Skip entries listed in ignore files (default); overrides `no_ignore` in the config file.
+    #[arg(long, overrides_with = "no_ignore")]
+    pub ignore: bool,
+
+    /// Descend at most this many directories below --path (0 is --path itself).
+    /// Defaults to unlimited for regex and glob searches and 1 for the TUI.
+    #[arg(long, env = "FERRY_MAX_DEPTH", value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Ignore entries less than this many directories below --path.
+    #[arg(long, env = "FERRY_MIN_DEPTH", value_name = "N", default_value_t = 0)]
+    pub min_depth: usize,
+
+    /// Include hidden (dot) files and directories (default).
+    #[arg(long, overrides_with = "no_hidden")]
+    pub hidden: bool,
+
+    /// Skip hidden (dot) files and directories.
+    #[arg(long, overrides_with = "hidden")]
+    pub no_hidden: bool,
+
+    /// Follow symbolic links to directories while walking.
+    #[arg(short = 'L', long, env = "FERRY_FOLLOW_LINKS", value_parser = BoolishValueParser::new())]
+    pub follow_links: bool,
+
+    /// Do not descend into directories on other file systems.
+    #[arg(long, env = "FERRY_ONE_FILE_SYSTEM", value_parser = BoolishValueParser::new())]
+    pub one_file_system: bool,
+
+    #[command(flatten)]
+    pub filters: FilterArgs,
+}
+
+/// Metadata conditions an entry must satisfy to be selected.
+#[derive(Args, Clone)]
+pub struct FilterArgs {
+    /// Only select entries of this size: +N for more, -N for less, N for exactly (units: b, k, M, G, T).
+    /// May be given multiple times, e.g. --size +1M --size -10M.
+    #[arg(long, env = "FERRY_SIZE", value_name = "SIZE", allow_hyphen_values = true)]
+    pub size: Vec<String>,
+
+    /// Only select entries modified within this age (e.g. 30m, 2h, 1d, 1w).
+    #[arg(long, env = "FERRY_NEWER", value_name = "AGE")]
+    pub newer: Option<String>,
+
+    /// Only select entries last modified longer ago than this age (e.g. 30m, 2h, 1d, 1w).
+    #[arg(long, env = "FERRY_OLDER", value_name = "AGE")]
+    pub older: Option<String>,
+
+    /// Only select entries of these types: f (file), d (directory), l (symlink). Comma-separated.
+    #[arg(long = "type", env = "FERRY_TYPE", value_name = "TYPE", value_delimiter = ',')]
+    pub entry_type: Vec<String>,
+
+    /// Only select entries owned by this user name or numeric uid.
+    #[arg(long, env = "FERRY_OWNER", value_name = "USER")]
+    pub owner: Option<String>,
+
+    /// Only select entries with this octal mode: 644 exactly, -644 all of these bits, /644 any of them.
+    #[arg(long, env = "FERRY_PERM", value_name = "MODE", allow_hyphen_values = true)]
+    pub perm: Option<String>,
+
+    /// Only select empty files and empty directories.
+    #[arg(long, env = "FERRY_EMPTY", value_parser = BoolishValueParser::new())]
+    pub empty: bool,
+}
+
+impl TraversalArgs {
+    /// Whether hidden entries are visited; the last of --hidden/--no-hidden wins.
+    pub fn hidden(&self) -> bool {
+        !self.no_hidden
+    }
+}
+
+/// Set operations over stored selections. The result replaces the selection chosen by --hold.
+#[derive(Args)]
+#[group(multiple = false, conflicts_with_all = ["items", "regex", "glob", "stdin", "interactive", "path"])]
+pub struct CombineArgs {
+    /// Select every file held by any of the named selections (e.g. --union a,b).
+    #[arg(long, value_delimiter = ',', value_name = "NAMES", num_args = 1..)]
+    pub union: Option<Vec<String>>,
+
+    /// Select only the files held by all of the named selections.
+    #[arg(long, value_delimiter = ',', value_name = "NAMES", num_args = 1..)]
+    pub intersect: Option<Vec<String>>,
+
+    /// Select the files of the first named selection that none of the others hold.
+    #[arg(long, value_delimiter = ',', value_name = "NAMES", num_args = 1..)]
+    pub subtract: Option<Vec<String>>,
+}
@@ -1,21 +1,44 @@
+use crate::cli::OutputFormat;
+use crate::settings::Settings;
+
 pub struct Config {
     pub silent: bool,
     pub verbose: bool,
+    pub hold: Option<String>,
+    pub format: OutputFormat,
+    pub settings: Settings,
 }
 
 impl Config {
-    pub fn new(silent: bool, verbose: bool) -> Self {
-        Config { silent, verbose }
+    pub fn new(silent: bool, verbose: bool, hold: Option<String>, format: OutputFormat, settings: Settings) -> Self {
+        Config { silent, verbose, hold, format, settings }
+    }
+
+    pub fn hold(&self) -> Option<&str> {
+        self.hold.as_deref()
+    }
+
+    /// Whether stdout is reserved for structured records (`--format` other than text).
+    pub fn is_machine_readable(&self) -> bool {
+        self.format != OutputFormat::Text
     }
 
     pub fn print_normal(&self, message: &str) {
         if !self.silent {
-            println!("{}", message);
+            self.print_message(message);
         }
     }
 
     pub fn print_verbose(&self, message: &str) {
         if self.verbose {
+            self.print_message(message);
+        }
+    }
+
+    fn print_message(&self, message: &str) {
+        if self.is_machine_readable() {
+            eprintln!("{}", message);
+        } else {
             println!("{}", message);
         }
     }
@@ -4,28 +4,83 @@ mod operations;
 mod modes;
 mod utils;
 mod config;
+mod output;
+mod settings;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
 use cli::{Cli, Commands};
 use config::Config;
+use operations::TransferOptions;
 
 fn main() {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
-    let config = Config::new(cli.silent, cli.verbose);
+    // `--silent` and `--verbose` may both be set when one comes from the
+    // environment; the command line wins, and only two of the same kind clash.
+    let (silent, verbose) = match (cli.silent, cli.verbose) {
+        (true, true) => match matches.value_source("silent").cmp(&matches.value_source("verbose")) {
+            std::cmp::Ordering::Greater => (true, false),
+            std::cmp::Ordering::Less => (false, true),
+            std::cmp::Ordering::Equal => Cli::command()
+                .error(ErrorKind::ArgumentConflict, "--silent and --verbose cannot be used together")
+                .exit(),
+        },
+        flags => flags,
+    };
+
+    let settings = match settings::load() {
+        Ok(settings) => settings,
+        // `ferry config` has to keep working to fix a broken config file.
+        Err(e) if matches!(cli.command, Commands::Config { .. }) => {
+            eprintln!("Warning: {}", e);
+            settings::Settings::default()
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let config = Config::new(silent, verbose, cli.hold, cli.format, settings);
 
     let result = match cli.command {
-        Commands::Select { items, regex, interactive, path, dry_run } => {
-            modes::handle_select_command(items, regex, interactive, path, dry_run, &config)
+        Commands::Select(args) => {
+            modes::handle_select_command(*args, &config)
+        }
+        Commands::Copy { conflict, keep } => {
+            let options = TransferOptions::resolve(&conflict, keep, &config.settings.transfer);
+            operations::handle_copy_command(options, &config)
+        }
+        Commands::Move { conflict } => {
+            let options = TransferOptions::resolve(&conflict, false, &config.settings.transfer);
+            operations::handle_move_command(options, &config)
         }
-        Commands::Copy { force } => {
-            operations::handle_copy_command(force, &config)
+        Commands::Drop { interactive, path, move_items, conflict, keep } => {
+            let mode = if move_items { operations::TransferMode::Move } else { operations::TransferMode::Copy };
+            let options = TransferOptions::resolve(&conflict, keep, &config.settings.transfer);
+            operations::handle_drop_command(interactive, path, mode, options, &config)
         }
-        Commands::Move { force } => {
-            operations::handle_move_command(force, &config)
+        Commands::List { absolute, relative, interactive } => {
+            // FERRY_LIST_RELATIVE gives way to --absolute and -i; only the
+            // flag itself clashes with them.
+            let relative_given = matches.subcommand_matches("list")
+                .and_then(|list| list.value_source("relative")) == Some(ValueSource::CommandLine);
+            if relative_given && (absolute || interactive) {
+                Cli::command()
+                    .error(ErrorKind::ArgumentConflict, "--relative cannot be used with --absolute or --interactive")
+                    .exit();
+            }
+            let relative = relative && !absolute;
+            if interactive {
+                operations::handle_manage_command(&config)
+            } else {
+                operations::handle_list_command(absolute, relative, &config)
+            }
         }
-        Commands::List { absolute, relative } => {
-            operations::handle_list_command(absolute, relative, &config)
+        Commands::Config { action } => {
+            settings::handle_config_command(action, &matches, &config)
         }
     };
 
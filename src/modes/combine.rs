@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use crate::cli::CombineArgs;
+use crate::config::Config;
+use crate::selection_store;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetOperation {
+    Union,
+    Intersect,
+    Subtract,
+}
+
+impl SetOperation {
+    fn label(self) -> &'static str {
+        match self {
+            SetOperation::Union => "union",
+            SetOperation::Intersect => "intersection",
+            SetOperation::Subtract => "difference",
+        }
+    }
+}
+
+pub fn requested_operation(args: &CombineArgs) -> Option<(SetOperation, &[String])> {
+    if let Some(names) = &args.union {
+        Some((SetOperation::Union, names))
+    } else if let Some(names) = &args.intersect {
+        Some((SetOperation::Intersect, names))
+    } else {
+        args.subtract.as_deref().map(|names| (SetOperation::Subtract, names))
+    }
+}
+
+/// Combines the named selections, keeping the order in which paths appear in the
+/// first selection (followed by later ones for a union).
+pub fn run_set_operation(operation: SetOperation, names: &[String], config: &Config) -> Result<Vec<PathBuf>, String> {
+    if names.len() < 2 {
+        return Err(format!("A selection {} needs at least two selection names, e.g. a,b.", operation.label()));
+    }
+
+    let mut selections = Vec::with_capacity(names.len());
+    for name in names {
+        let paths = selection_store::read_existing_selection(name.trim())?;
+        config.print_verbose(&format!("Selection '{}' holds {} items", name.trim(), paths.len()));
+        selections.push(paths);
+    }
+
+    let (first, rest) = selections.split_first().expect("at least two selections");
+    // Each path is kept once, where it first appears.
+    let mut seen: HashSet<&PathBuf> = HashSet::new();
+    let combined: Vec<PathBuf> = match operation {
+        SetOperation::Union => selections.iter()
+            .flatten()
+            .filter(|p| seen.insert(p))
+            .cloned()
+            .collect(),
+        SetOperation::Intersect => first.iter()
+            .filter(|p| rest.iter().all(|other| other.contains(p)) && seen.insert(p))
+            .cloned()
+            .collect(),
+        SetOperation::Subtract => first.iter()
+            .filter(|p| !rest.iter().any(|other| other.contains(p)) && seen.insert(p))
+            .cloned()
+            .collect(),
+    };
+    Ok(combined)
+}
@@ -1,30 +1,180 @@
+use std::collections::HashSet;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
-use regex::Regex;
-use walkdir::WalkDir;
-use crate::utils::fs_helpers;
+use std::sync::Arc;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::DirEntry;
+use regex::bytes::RegexBuilder;
+use crate::cli::{MatchTarget, TraversalArgs};
+use crate::utils::{fs_helpers, walker};
 use crate::config::Config;
+use super::filters::MetadataFilter;
 
-pub fn run_regex_selection(start_path: &Path, pattern_str: &str, config: &Config) -> Result<Vec<PathBuf>, String> { 
-    let regex = Regex::new(pattern_str)
-        .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+/// A set of glob patterns. Patterns containing a '/' are matched against the path
+/// relative to the search root, the others against the file name alone.
+pub struct GlobPatterns {
+    by_name: GlobSet,
+    by_path: GlobSet,
+    is_empty: bool,
+}
+
+impl GlobPatterns {
+    pub fn new(patterns: &[String], ignore_case: bool) -> Result<Self, String> {
+        let mut by_name = GlobSetBuilder::new();
+        let mut by_path = GlobSetBuilder::new();
+        for pattern in patterns {
+            let trimmed = pattern.trim_start_matches("./");
+            let glob = GlobBuilder::new(trimmed)
+                .case_insensitive(ignore_case)
+                .literal_separator(true)
+                .backslash_escape(true)
+                .build()
+                .map_err(|e| format!("Invalid glob pattern '{}': {}", pattern, e))?;
+            if trimmed.contains('/') {
+                by_path.add(glob);
+            } else {
+                by_name.add(glob);
+            }
+        }
+        Ok(GlobPatterns {
+            by_name: by_name.build().map_err(|e| format!("Invalid glob patterns: {}", e))?,
+            by_path: by_path.build().map_err(|e| format!("Invalid glob patterns: {}", e))?,
+            is_empty: patterns.is_empty(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.is_empty
+    }
+
+    pub fn is_match(&self, relative_path: &Path) -> bool {
+        if self.by_path.is_match(relative_path) {
+            return true;
+        }
+        relative_path.file_name()
+            .map(|name| self.by_name.is_match(name))
+            .unwrap_or(false)
+    }
+}
+
+/// The --include/--exclude rules applied while walking a directory tree.
+pub struct TraversalFilter {
+    include: GlobPatterns,
+    exclude: GlobPatterns,
+}
+
+impl TraversalFilter {
+    pub fn from_args(args: &TraversalArgs) -> Result<Self, String> {
+        Ok(TraversalFilter {
+            include: GlobPatterns::new(&args.include, args.ignore_case)?,
+            exclude: GlobPatterns::new(&args.exclude, args.ignore_case)?,
+        })
+    }
+
+    /// Excluded directories are pruned, so nothing below them is visited.
+    pub fn is_excluded(&self, relative_path: &Path) -> bool {
+        !relative_path.as_os_str().is_empty() && self.exclude.is_match(relative_path)
+    }
+
+    pub fn is_included(&self, relative_path: &Path) -> bool {
+        self.include.is_empty() || self.include.is_match(relative_path)
+    }
+}
+
+pub fn relative_to<'a>(path: &'a Path, start_path: &Path) -> &'a Path {
+    path.strip_prefix(start_path).unwrap_or(path)
+}
 
+/// Builds the walker shared by every selection mode: ignore files are honoured
+/// and --exclude prunes whole subtrees.
+pub fn filtered_walker(start_path: &Path, traversal: &TraversalArgs) -> Result<ignore::WalkBuilder, String> {
+    let filter = Arc::new(TraversalFilter::from_args(traversal)?);
+    let root = start_path.to_path_buf();
+    let no_ignore = traversal.no_ignore;
+
+    let mut builder = walker::new_walker(start_path, traversal);
+    builder.filter_entry(move |entry| {
+        let is_pruned_vcs = !no_ignore && walker::is_vcs_directory(entry);
+        !is_pruned_vcs && !filter.is_excluded(relative_to(entry.path(), &root))
+    });
+    Ok(builder)
+}
+
+/// Walks `start_path` and collects the canonical paths of every entry that passes
+/// the traversal filter and `is_selected`.
+fn walk_and_select<F>(
+    start_path: &Path,
+    traversal: &TraversalArgs,
+    config: &Config,
+    mut is_selected: F,
+) -> Result<Vec<PathBuf>, String>
+where
+    F: FnMut(&DirEntry, &Path) -> bool,
+{
+    let filter = TraversalFilter::from_args(traversal)?;
+    let metadata_filter = MetadataFilter::from_args(&traversal.filters)?;
     let mut selected_paths = Vec::new();
+    // Symlinked entries can canonicalize to a path already selected.
+    let mut seen = HashSet::new();
 
-    for entry_result in WalkDir::new(start_path) {
+    for entry_result in filtered_walker(start_path, traversal)?.build() {
         let entry = entry_result
             .map_err(|e| format!("Error traversing directory {}: {}", start_path.display(), e))?;
-        let path = entry.path();
+        let relative_path = relative_to(entry.path(), start_path);
 
-        if regex.is_match(path.to_str().unwrap_or("")) {
-            if let Some(abs_path) = fs_helpers::canonicalize_path(path, config)? {
-                config.print_verbose(&format!("Selected by regex: {}", abs_path.display()));
-                selected_paths.push(abs_path);
-            }
+        if entry.depth() >= traversal.min_depth
+            && filter.is_included(relative_path)
+            && is_selected(&entry, relative_path)
+            && metadata_filter.matches(&entry)
+            && let Some(abs_path) = fs_helpers::canonicalize_path(entry.path(), config)?
+            && seen.insert(abs_path.clone()) {
+            config.print_verbose(&format!("Selected: {}", abs_path.display()));
+            selected_paths.push(abs_path);
         }
     }
     Ok(selected_paths)
 }
 
+/// Matches the regex against the raw bytes of the chosen part of the path, so
+/// file names that are not valid UTF-8 can still be selected.
+pub fn run_regex_selection(
+    start_path: &Path,
+    pattern_str: &str,
+    match_on: MatchTarget,
+    traversal: &TraversalArgs,
+    config: &Config,
+) -> Result<Vec<PathBuf>, String> {
+    let regex = RegexBuilder::new(pattern_str)
+        .case_insensitive(traversal.ignore_case)
+        .build()
+        .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+
+    walk_and_select(start_path, traversal, config, |entry, relative_path| {
+        let subject = match match_on {
+            MatchTarget::Name => {
+                if entry.depth() == 0 {
+                    return false;
+                }
+                entry.file_name().to_os_string()
+            }
+            MatchTarget::Relpath => relative_path.as_os_str().to_os_string(),
+            MatchTarget::Abspath => match std::path::absolute(entry.path()) {
+                Ok(abs_path) => abs_path.into_os_string(),
+                Err(_) => return false,
+            },
+        };
+        regex.is_match(subject.as_encoded_bytes())
+    })
+}
+
+pub fn run_glob_selection(start_path: &Path, pattern_str: &str, traversal: &TraversalArgs, config: &Config) -> Result<Vec<PathBuf>, String> {
+    let patterns = GlobPatterns::new(&[pattern_str.to_string()], traversal.ignore_case)?;
+
+    walk_and_select(start_path, traversal, config, |entry, relative_path| {
+        entry.depth() > 0 && patterns.is_match(relative_path)
+    })
+}
+
 pub fn validate_and_canonicalize_items(item_strs: &[String], config: &Config) -> Result<Vec<PathBuf>, String> {
     let mut selected_paths = Vec::new();
     for p_str in item_strs {
@@ -36,4 +186,30 @@ pub fn validate_and_canonicalize_items(item_strs: &[String], config: &Config) ->
         }
     }
     Ok(selected_paths)
-}
\ No newline at end of file
+}
+
+/// Reads newline- or NUL-delimited paths from standard input. Blank entries are
+/// skipped and a trailing carriage return is dropped from newline-delimited input.
+pub fn read_items_from_stdin(null_delimited: bool, config: &Config) -> Result<Vec<PathBuf>, String> {
+    let mut input = Vec::new();
+    io::stdin().lock().read_to_end(&mut input)
+        .map_err(|e| format!("Failed to read paths from standard input: {}", e))?;
+
+    let delimiter = if null_delimited { b'\0' } else { b'\n' };
+    let mut selected_paths = Vec::new();
+    // Alongside the list, which keeps the input order, for fast duplicate checks.
+    let mut seen = HashSet::new();
+    for raw in input.split(|b| *b == delimiter) {
+        let raw = if null_delimited { raw } else { raw.strip_suffix(b"\r").unwrap_or(raw) };
+        if raw.is_empty() {
+            continue;
+        }
+        let path = fs_helpers::path_from_bytes(raw);
+        if let Some(abs_path) = fs_helpers::canonicalize_path(&path, config)?
+            && seen.insert(abs_path.clone()) {
+            config.print_verbose(&format!("Selected from stdin: {}", abs_path.display()));
+            selected_paths.push(abs_path);
+        }
+    }
+    Ok(selected_paths)
+}
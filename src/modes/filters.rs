@@ -0,0 +1,267 @@
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use ignore::DirEntry;
+use crate::cli::FilterArgs;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    Equal,
+    Greater,
+}
+
+/// A `--size` argument such as `+100M`, `-4k` or `512`. Sizes are rounded up to
+/// the unit before comparing, like `find -size`.
+#[derive(Clone, Copy, Debug)]
+struct SizeFilter {
+    comparison: Comparison,
+    amount: u64,
+    unit: u64,
+}
+
+impl SizeFilter {
+    fn parse(input: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid --size '{}'. Expected e.g. +100M, -4k or 512 (units: b, k, M, G, T).", input);
+        let (comparison, rest) = split_comparison(input);
+        let rest = rest.strip_suffix(['B', 'b']).filter(|r| r.ends_with(|c: char| c.is_ascii_alphabetic())).unwrap_or(rest);
+        let digits_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let (digits, suffix) = rest.split_at(digits_end);
+        let amount: u64 = digits.parse().map_err(|_| invalid())?;
+        let unit = match suffix {
+            "" | "b" | "B" => 1,
+            "k" | "K" => 1 << 10,
+            "m" | "M" => 1 << 20,
+            "g" | "G" => 1 << 30,
+            "t" | "T" => 1 << 40,
+            _ => return Err(invalid()),
+        };
+        Ok(SizeFilter { comparison, amount, unit })
+    }
+
+    fn matches(&self, size: u64) -> bool {
+        let size_in_units = size.div_ceil(self.unit);
+        match self.comparison {
+            Comparison::Less => size_in_units < self.amount,
+            Comparison::Equal => size_in_units == self.amount,
+            Comparison::Greater => size_in_units > self.amount,
+        }
+    }
+}
+
+fn split_comparison(input: &str) -> (Comparison, &str) {
+    if let Some(rest) = input.strip_prefix('+') {
+        (Comparison::Greater, rest)
+    } else if let Some(rest) = input.strip_prefix('-') {
+        (Comparison::Less, rest)
+    } else {
+        (Comparison::Equal, input)
+    }
+}
+
+/// Parses an age such as `30s`, `15m`, `2h`, `1d` or `1w`. A bare number means days.
+fn parse_age(flag: &str, input: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid {} '{}'. Expected an age such as 30s, 15m, 2h, 1d or 1w.", flag, input);
+    let digits_end = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (digits, suffix) = input.split_at(digits_end);
+    let amount: u64 = digits.parse().map_err(|_| invalid())?;
+    let seconds = match suffix {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(seconds)))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntryType {
+    File,
+    Directory,
+    Symlink,
+}
+
+impl EntryType {
+    fn parse(input: &str) -> Result<Self, String> {
+        match input {
+            "f" | "file" => Ok(EntryType::File),
+            "d" | "dir" | "directory" => Ok(EntryType::Directory),
+            "l" | "link" | "symlink" => Ok(EntryType::Symlink),
+            _ => Err(format!("Invalid --type '{}'. Expected f, d or l.", input)),
+        }
+    }
+
+    fn of(metadata: &Metadata) -> Option<Self> {
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            Some(EntryType::Symlink)
+        } else if file_type.is_dir() {
+            Some(EntryType::Directory)
+        } else if file_type.is_file() {
+            Some(EntryType::File)
+        } else {
+            None
+        }
+    }
+}
+
+/// A `--perm` argument in the style of `find -perm`: `644` requires exactly these
+/// bits, `-644` requires at least all of them and `/644` at least one of them.
+#[derive(Clone, Copy, Debug)]
+enum PermFilter {
+    Exact(u32),
+    AllOf(u32),
+    AnyOf(u32),
+}
+
+impl PermFilter {
+    fn parse(input: &str) -> Result<Self, String> {
+        let parse_mode = |mode: &str| u32::from_str_radix(mode, 8)
+            .ok()
+            .filter(|m| *m <= 0o7777)
+            .ok_or_else(|| format!("Invalid --perm '{}'. Expected an octal mode such as 644, -644 or /111.", input));
+        if let Some(mode) = input.strip_prefix('-') {
+            Ok(PermFilter::AllOf(parse_mode(mode)?))
+        } else if let Some(mode) = input.strip_prefix('/') {
+            Ok(PermFilter::AnyOf(parse_mode(mode)?))
+        } else {
+            Ok(PermFilter::Exact(parse_mode(input)?))
+        }
+    }
+
+    #[cfg(unix)]
+    fn matches(&self, metadata: &Metadata) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o7777;
+        match *self {
+            PermFilter::Exact(bits) => mode == bits,
+            PermFilter::AllOf(bits) => mode & bits == bits,
+            PermFilter::AnyOf(bits) => bits == 0 || mode & bits != 0,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn matches(&self, _metadata: &Metadata) -> bool {
+        false
+    }
+}
+
+#[cfg(unix)]
+fn resolve_owner(input: &str) -> Result<u32, String> {
+    if let Ok(uid) = input.parse::<u32>() {
+        return Ok(uid);
+    }
+    uzers::get_user_by_name(input)
+        .map(|user| user.uid())
+        .ok_or_else(|| format!("Unknown user '{}' given to --owner.", input))
+}
+
+#[cfg(not(unix))]
+fn resolve_owner(_input: &str) -> Result<u32, String> {
+    Err("--owner is only supported on Unix-like systems.".to_string())
+}
+
+#[cfg(unix)]
+fn owner_of(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn owner_of(_metadata: &Metadata) -> Option<u32> {
+    None
+}
+
+/// The metadata conditions given to `ferry select`. An entry is kept only if it
+/// satisfies every condition.
+pub struct MetadataFilter {
+    sizes: Vec<SizeFilter>,
+    newer_than: Option<SystemTime>,
+    older_than: Option<SystemTime>,
+    types: Vec<EntryType>,
+    owner: Option<u32>,
+    perm: Option<PermFilter>,
+    empty: bool,
+}
+
+impl MetadataFilter {
+    pub fn from_args(args: &FilterArgs) -> Result<Self, String> {
+        let now = SystemTime::now();
+        let cutoff = |flag: &str, age: &Option<String>| -> Result<Option<SystemTime>, String> {
+            age.as_deref()
+                .map(|a| parse_age(flag, a).map(|d| now.checked_sub(d).unwrap_or(SystemTime::UNIX_EPOCH)))
+                .transpose()
+        };
+
+        Ok(MetadataFilter {
+            sizes: args.size.iter().map(|s| SizeFilter::parse(s)).collect::<Result<_, _>>()?,
+            newer_than: cutoff("--newer", &args.newer)?,
+            older_than: cutoff("--older", &args.older)?,
+            types: args.entry_type.iter().map(|t| EntryType::parse(t)).collect::<Result<_, _>>()?,
+            owner: args.owner.as_deref().map(resolve_owner).transpose()?,
+            perm: args.perm.as_deref().map(PermFilter::parse).transpose()?,
+            empty: args.empty,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.sizes.is_empty()
+            || self.newer_than.is_some()
+            || self.older_than.is_some()
+            || !self.types.is_empty()
+            || self.owner.is_some()
+            || self.perm.is_some()
+            || self.empty
+    }
+
+    /// Checks a walked entry against every condition. Symlinks are judged by the
+    /// link itself, or by their target when the walk follows links.
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            return false;
+        };
+        self.matches_metadata(entry.path(), &metadata)
+    }
+
+    fn matches_metadata(&self, path: &Path, metadata: &Metadata) -> bool {
+        if !self.types.is_empty() && !EntryType::of(metadata).is_some_and(|t| self.types.contains(&t)) {
+            return false;
+        }
+        if !self.sizes.iter().all(|s| s.matches(metadata.len())) {
+            return false;
+        }
+        if self.newer_than.is_some() || self.older_than.is_some() {
+            let Ok(modified) = metadata.modified() else {
+                return false;
+            };
+            if self.newer_than.is_some_and(|cutoff| modified < cutoff)
+                || self.older_than.is_some_and(|cutoff| modified > cutoff) {
+                return false;
+            }
+        }
+        if let Some(uid) = self.owner
+            && owner_of(metadata) != Some(uid) {
+            return false;
+        }
+        if let Some(perm) = &self.perm
+            && !perm.matches(metadata) {
+            return false;
+        }
+        if self.empty {
+            let is_empty = if metadata.is_dir() {
+                fs::read_dir(path).map(|mut entries| entries.next().is_none()).unwrap_or(false)
+            } else {
+                metadata.is_file() && metadata.len() == 0
+            };
+            if !is_empty {
+                return false;
+            }
+        }
+        true
+    }
+}
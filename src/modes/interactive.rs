@@ -1,163 +1,893 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::io;
-use crossterm::{
-    event::{self, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use std::time::{Duration, Instant};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
-    backend::CrosstermBackend,
-    widgets::{ListItem, List, Block, Borders},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Cell, ListItem, List, ListState, Block, Borders, Clear, HighlightSpacing, Paragraph, Row,
+        Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState,
+    },
     Terminal,
 };
-use walkdir::WalkDir;
+use crate::cli::TraversalArgs;
 use crate::config::Config;
+use crate::selection_store;
+use crate::modes::explicit::GlobPatterns;
+use crate::utils::format;
+use crate::utils::terminal::TerminalGuard;
+
+mod destination;
+mod fuzzy;
+mod hold;
+mod keymap;
+mod listing;
+mod manager;
+mod preview;
+mod selection;
+mod sizes;
+mod theme;
+
+pub use destination::{run_drop_tui, DropOutcome};
+pub use manager::{run_manager_tui, ManagerOutcome};
+use hold::{HoldMark, HoldPanel};
+use keymap::{Command, Keymap, Screen};
+use listing::{DirectoryLister, Entry, SortKey, SortOrder};
+use preview::Preview;
+use theme::Theme;
+use selection::{Selection, SelectionState};
+use sizes::SizeCache;
+
+/// Below this terminal width the preview pane is hidden to leave room for the list.
+const MIN_WIDTH_FOR_PREVIEW: u16 = 60;
+/// Two clicks on the same row within this interval count as a double click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// How often the size of the selection is updated while it is measured.
+const SIZE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Rows the cursor moves per scroll wheel step.
+const MOUSE_SCROLL_ROWS: isize = 3;
+const NAME_COLUMN_MIN_WIDTH: u16 = 20;
+const SIZE_COLUMN_WIDTH: u16 = 10;
+const MODIFIED_COLUMN_WIDTH: u16 = 16;
+const PERMISSIONS_COLUMN_WIDTH: u16 = 13;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ViewMode {
+    /// One directory at a time.
+    Browser,
+    /// A collapsible tree of everything below the start directory.
+    Tree,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SelectionChange {
+    Select,
+    Deselect,
+    Toggle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InputMode {
+    Normal,
+    /// Typing goes into the fuzzy filter query.
+    Filter,
+    /// Typing goes into the glob pattern used by "select all matching".
+    Pattern,
+}
+
+/// How the user left the TUI.
+pub enum TuiOutcome {
+    /// The user reviewed and saved; holds the complete new selection, which may be empty.
+    Confirmed(Vec<PathBuf>),
+    /// The user quit, or there was nothing to select; the stored selection is unchanged.
+    Cancelled,
+}
 
 struct TuiState {
-    items: Vec<PathBuf>,
-    selected_indices: Vec<usize>,
-    current_scroll_index: usize,
-    scroll_offset: usize,
+    lister: DirectoryLister,
+    keymap: Keymap,
+    theme: Theme,
+    show_help: bool,
+    help_scroll: u16,
+    /// The summary shown before saving is open.
+    confirming: bool,
+    root: PathBuf,
+    current_dir: PathBuf,
+    view: ViewMode,
+    sort: SortOrder,
+    expanded: HashSet<PathBuf>,
+    items: Vec<Entry>,
+    selected: Selection,
+    query: String,
+    input_mode: InputMode,
+    pattern_input: String,
+    visual_anchor: Option<usize>,
+    sizes: SizeCache,
+    /// The size of the selection, and whether some of it is still being measured.
+    selected_size: (u64, bool),
+    visible: Vec<VisibleItem>,
+    message: Option<String>,
+    show_preview: bool,
+    preview: Option<Preview>,
+    hold: HoldPanel,
+    /// The highlighted row of `visible` and how far the list is scrolled.
+    table_state: TableState,
     terminal_height: usize,
+    /// Where the list and the hold panel were last drawn, to map mouse clicks.
+    list_area: Rect,
+    hold_area: Option<Rect>,
+    /// The row and time of the last click, to detect double clicks.
+    last_click: Option<(usize, Instant)>,
+}
+
+/// An entry of `items` that passes the current filter, with the positions of
+/// the characters that matched the query.
+struct VisibleItem {
+    index: usize,
+    positions: Vec<usize>,
 }
 
 impl TuiState {
-    fn new(items: Vec<PathBuf>, terminal_height: u16) -> Self {
-        TuiState {
+    fn new(
+        lister: DirectoryLister,
+        keymap: Keymap,
+        theme: Theme,
+        hold: HoldPanel,
+        start_path: &Path,
+        items: Vec<Entry>,
+        terminal_height: u16,
+    ) -> Self {
+        let mut state = TuiState {
+            lister,
+            keymap,
+            theme,
+            show_help: false,
+            help_scroll: 0,
+            confirming: false,
+            root: start_path.to_path_buf(),
+            current_dir: start_path.to_path_buf(),
+            view: ViewMode::Browser,
+            sort: SortOrder::default(),
+            expanded: HashSet::new(),
             items,
-            selected_indices: Vec::new(),
-            current_scroll_index: 0,
-            scroll_offset: 0,
+            selected: Selection::default(),
+            query: String::new(),
+            input_mode: InputMode::Normal,
+            pattern_input: String::new(),
+            visual_anchor: None,
+            sizes: SizeCache::new(),
+            selected_size: (0, false),
+            visible: Vec::new(),
+            message: None,
+            show_preview: true,
+            preview: None,
+            hold,
+            table_state: TableState::default(),
             terminal_height: terminal_height as usize,
+            list_area: Rect::default(),
+            hold_area: None,
+            last_click: None,
+        };
+        let held = state.hold.held().to_vec();
+        state.update_selection(&held, SelectionChange::Select);
+        state.apply_filter();
+        state
+    }
+
+    fn label(&self, entry: &Entry) -> String {
+        let path = &entry.path;
+        let relative = match self.view {
+            ViewMode::Browser => path.strip_prefix(&self.current_dir).ok(),
+            ViewMode::Tree => None,
+        };
+        let mut label = match relative {
+            Some(stripped) if !stripped.as_os_str().is_empty() => stripped.display().to_string(),
+            _ => path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned(),
+        };
+        if entry.is_dir {
+            label.push('/');
+        }
+        label
+    }
+
+    /// Recomputes `visible` from the query, best matches first. Toggled
+    /// selections are kept by path, so they survive any change of the filter.
+    fn apply_filter(&mut self) {
+        let mut matches: Vec<(i64, VisibleItem)> = self.items.iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                fuzzy::fuzzy_match(&self.query, &self.label(entry))
+                    .map(|m| (m.score, VisibleItem { index, positions: m.positions }))
+            })
+            .collect();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.visible = matches.into_iter().map(|(_, item)| item).collect();
+        self.visual_anchor = None;
+        self.table_state = TableState::default();
+        self.move_to(0);
+    }
+
+    fn push_query_char(&mut self, c: char) {
+        self.query.push(c);
+        self.apply_filter();
+    }
+
+    fn pop_query_char(&mut self) {
+        if self.query.pop().is_some() {
+            self.apply_filter();
+        }
+    }
+
+    fn clear_query(&mut self) {
+        if !self.query.is_empty() {
+            self.query.clear();
+            self.apply_filter();
+        }
+    }
+
+    fn current_entry(&self) -> Option<&Entry> {
+        self.visible.get(self.cursor())
+            .and_then(|v| self.items.get(v.index))
+    }
+
+    /// Lists `dir` and makes it the current directory. On failure the browser
+    /// stays where it is and shows the error.
+    fn change_directory(&mut self, dir: PathBuf) -> bool {
+        match self.lister.list(&dir) {
+            Ok(mut items) => {
+                self.sort.sort(&mut items);
+                self.current_dir = dir;
+                self.items = items;
+                self.query.clear();
+                self.input_mode = InputMode::Normal;
+                self.message = None;
+                self.apply_filter();
+                true
+            }
+            Err(e) => {
+                self.message = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Rebuilds the rows of the tree view and keeps the cursor on `focus`.
+    fn rebuild_tree(&mut self, focus: Option<PathBuf>) {
+        let mut rows = Vec::new();
+        let mut pending: Vec<Entry> = self.children(&self.root, 0).into_iter().rev().collect();
+        while let Some(entry) = pending.pop() {
+            if entry.is_dir && self.expanded.contains(&entry.path) {
+                pending.extend(self.children(&entry.path, entry.depth + 1).into_iter().rev());
+            }
+            rows.push(entry);
+        }
+        self.items = rows;
+        self.apply_filter();
+        if let Some(path) = focus {
+            self.move_to_path(&path);
+        }
+    }
+
+    fn children(&self, dir: &Path, depth: usize) -> Vec<Entry> {
+        let mut children: Vec<Entry> = self.lister.list_children(dir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| Entry { depth, ..entry })
+            .collect();
+        self.sort.sort(&mut children);
+        children
+    }
+
+    /// Re-sorts the list after the sort order changed, keeping the cursor on
+    /// the highlighted entry.
+    fn resort(&mut self) {
+        let focus = self.current_entry().map(|e| e.path.clone());
+        match self.view {
+            ViewMode::Browser => {
+                self.sort.sort(&mut self.items);
+                self.apply_filter();
+                if let Some(path) = focus {
+                    self.move_to_path(&path);
+                }
+            }
+            ViewMode::Tree => self.rebuild_tree(focus),
+        }
+        self.message = Some(format!(
+            "Sorted by {}{}{}",
+            sort_key_label(self.sort.key).to_lowercase(),
+            if self.sort.descending { ", descending" } else { "" },
+            if self.sort.directories_first { ", directories first" } else { "" },
+        ));
+    }
+
+    /// Switches between the directory browser and the tree view, keeping the
+    /// highlighted entry when it exists in both.
+    fn toggle_view(&mut self) {
+        let focus = self.current_entry().map(|e| e.path.clone());
+        self.query.clear();
+        self.input_mode = InputMode::Normal;
+        match self.view {
+            ViewMode::Browser => {
+                self.view = ViewMode::Tree;
+                if let Some(path) = &focus {
+                    let mut dir = path.parent();
+                    while let Some(d) = dir.filter(|d| d.starts_with(&self.root) && *d != self.root) {
+                        self.expanded.insert(d.to_path_buf());
+                        dir = d.parent();
+                    }
+                }
+                self.rebuild_tree(focus);
+            }
+            ViewMode::Tree => {
+                self.view = ViewMode::Browser;
+                let dir = focus.as_deref()
+                    .and_then(Path::parent)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|| self.root.clone());
+                if self.change_directory(dir) && let Some(path) = focus {
+                    self.move_to_path(&path);
+                }
+            }
+        }
+    }
+
+    /// Browser: opens the highlighted directory. Tree: expands it, or moves to
+    /// its first child if it is already expanded.
+    fn enter_directory(&mut self) {
+        let Some(entry) = self.current_entry().filter(|e| e.is_dir).cloned() else {
+            return;
+        };
+        match self.view {
+            ViewMode::Browser => {
+                self.change_directory(entry.path);
+            }
+            ViewMode::Tree => {
+                if self.expanded.insert(entry.path.clone()) {
+                    self.rebuild_tree(Some(entry.path));
+                } else {
+                    self.move_down();
+                }
+            }
         }
     }
 
+    /// Browser: goes to the parent directory, keeping the cursor on the directory
+    /// just left. Tree: collapses the highlighted directory, or moves to its parent.
+    fn leave_directory(&mut self) {
+        match self.view {
+            ViewMode::Browser => {
+                let Some(parent) = self.current_dir.parent().map(Path::to_path_buf) else {
+                    return;
+                };
+                let previous = self.current_dir.clone();
+                if self.change_directory(parent) {
+                    self.move_to_path(&previous);
+                }
+            }
+            ViewMode::Tree => {
+                let Some(entry) = self.current_entry().cloned() else {
+                    return;
+                };
+                if entry.is_dir && self.expanded.remove(&entry.path) {
+                    self.rebuild_tree(Some(entry.path));
+                } else if let Some(parent) = entry.path.parent().filter(|p| *p != self.root) {
+                    let parent = parent.to_path_buf();
+                    self.move_to_path(&parent);
+                }
+            }
+        }
+    }
+
+    fn move_to_path(&mut self, path: &Path) {
+        if let Some(position) = self.visible.iter().position(|v| self.items[v.index].path == path) {
+            self.move_to(position);
+        }
+    }
+
+    /// Toggles the highlighted entry. Selecting a directory selects everything
+    /// below it; deselecting something inside a selected directory keeps the
+    /// rest of that directory selected.
     fn toggle_selection(&mut self) {
-        if self.items.is_empty() {
+        let Some(path) = self.current_entry().map(|e| e.path.clone()) else {
             return;
+        };
+        self.update_selection(&[path], SelectionChange::Toggle);
+    }
+
+    fn visible_paths(&self) -> Vec<PathBuf> {
+        self.visible.iter().map(|v| self.items[v.index].path.clone()).collect()
+    }
+
+    fn update_selection(&mut self, paths: &[PathBuf], change: SelectionChange) {
+        // Unfiltered, so that splitting up a selected directory keeps its
+        // ignored, hidden and filtered-out contents selected.
+        let children = |dir: &Path| -> Vec<PathBuf> {
+            std::fs::read_dir(dir)
+                .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+                .unwrap_or_default()
+        };
+        match change {
+            SelectionChange::Select => paths.iter().for_each(|path| self.selected.select(path)),
+            SelectionChange::Deselect => paths.iter().for_each(|path| self.selected.deselect(path, children)),
+            SelectionChange::Toggle => self.selected.invert(paths, children),
         }
-        let index = self.current_scroll_index;
-        if let Some(pos) = self.selected_indices.iter().position(|&i| i == index) {
-            self.selected_indices.remove(pos);
-        } else {
-            self.selected_indices.push(index);
-            self.selected_indices.sort_unstable();
+        self.selected_size = self.sizes.total(self.selected.paths());
+    }
+
+    /// Picks up sizes measured in the background; true if the total changed.
+    fn collect_sizes(&mut self) -> bool {
+        if !self.sizes.collect() {
+            return false;
         }
+        self.selected_size = self.sizes.total(self.selected.paths());
+        true
     }
 
-    fn move_up(&mut self) {
-        if self.current_scroll_index > 0 {
-            self.current_scroll_index -= 1;
-            if self.current_scroll_index < self.scroll_offset {
-                self.scroll_offset -= 1;
+    /// The selection's size for the status line, marked while incomplete.
+    fn selected_size_label(&self) -> String {
+        let (size, incomplete) = self.selected_size;
+        let label = format::human_size(size);
+        if incomplete { format!("{}+", label) } else { label }
+    }
+
+    /// Applies `change` to every entry that passes the current filter.
+    fn update_all_visible(&mut self, change: SelectionChange) {
+        let paths = self.visible_paths();
+        self.update_selection(&paths, change);
+    }
+
+    /// Starts a visual range at the cursor, or selects the range and ends it.
+    fn toggle_visual_range(&mut self) {
+        match self.visual_anchor.take() {
+            None => self.visual_anchor = Some(self.cursor()),
+            Some(anchor) => {
+                let (start, end) = ordered(anchor, self.cursor());
+                let paths: Vec<PathBuf> = self.visible[start..=end].iter()
+                    .map(|v| self.items[v.index].path.clone())
+                    .collect();
+                self.update_selection(&paths, SelectionChange::Select);
             }
         }
     }
 
-    fn move_down(&mut self) {
-        if self.current_scroll_index < self.items.len().saturating_sub(1) {
-            self.current_scroll_index += 1;
-            let visible_height = self.terminal_height.saturating_sub(2);
-            if self.current_scroll_index >= self.scroll_offset + visible_height {
-                self.scroll_offset += 1;
+    fn in_visual_range(&self, index: usize) -> bool {
+        self.visual_anchor.is_some_and(|anchor| {
+            let (start, end) = ordered(anchor, self.cursor());
+            (start..=end).contains(&index)
+        })
+    }
+
+    /// Selects every visible entry whose name matches the glob in `pattern_input`.
+    fn select_matching_pattern(&mut self) {
+        let pattern = std::mem::take(&mut self.pattern_input);
+        if pattern.is_empty() {
+            return;
+        }
+        let patterns = match GlobPatterns::new(std::slice::from_ref(&pattern), false) {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                self.message = Some(e);
+                return;
+            }
+        };
+        let matching: Vec<PathBuf> = self.visible_paths()
+            .into_iter()
+            .filter(|p| patterns.is_match(p.strip_prefix(&self.current_dir).unwrap_or(p)))
+            .collect();
+        self.message = Some(format!("{} entries matched '{}'", matching.len(), pattern));
+        self.update_selection(&matching, SelectionChange::Select);
+    }
+
+    /// Removes the highlighted entry of the hold panel from the selection, or
+    /// restores it if it was removed earlier.
+    fn toggle_hold_entry(&mut self) {
+        let entries = self.hold.entries(&self.selected);
+        let Some((path, mark)) = entries.get(self.hold.cursor).cloned() else {
+            return;
+        };
+        let change = if mark == HoldMark::Removed { SelectionChange::Select } else { SelectionChange::Deselect };
+        self.update_selection(&[path], change);
+    }
+
+    fn scroll_help(&mut self, delta: isize) {
+        // The popup uses the full screen height, one row more than the list area.
+        let rows_shown = (self.terminal_height + 1).saturating_sub(2);
+        let commands = Command::ALL.iter().filter(|c| c.available_in(Screen::Select)).count();
+        let max_scroll = commands.saturating_sub(rows_shown);
+        self.help_scroll = (self.help_scroll as usize).saturating_add_signed(delta).min(max_scroll) as u16;
+    }
+
+    /// Clicking a row highlights it, clicking its checkbox toggles it and a
+    /// double click opens a directory or toggles a file. The wheel moves the
+    /// cursor of whatever is under the pointer.
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let position = ratatui::layout::Position::new(mouse.column, mouse.row);
+        let over_hold = self.hold.visible && self.hold_area.is_some_and(|area| area.contains(position));
+        if self.confirming {
+            return;
+        }
+        if self.show_help {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => self.scroll_help(-1),
+                MouseEventKind::ScrollDown => self.scroll_help(1),
+                MouseEventKind::Down(_) => self.show_help = false,
+                _ => {}
+            }
+            return;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let delta = if mouse.kind == MouseEventKind::ScrollUp { -MOUSE_SCROLL_ROWS } else { MOUSE_SCROLL_ROWS };
+                if over_hold {
+                    let len = self.hold.entries(&self.selected).len();
+                    self.hold.move_by(delta, len);
+                } else {
+                    self.move_by(delta);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) if !over_hold => {
+                let Some(index) = self.row_at(mouse.column, mouse.row) else {
+                    return;
+                };
+                // Clicking the list gives it the focus back from the hold panel.
+                self.hold.visible = false;
+                self.message = None;
+                let now = Instant::now();
+                let is_double_click = self.last_click
+                    .is_some_and(|(row, time)| row == index && now.duration_since(time) <= DOUBLE_CLICK_INTERVAL);
+                self.last_click = if is_double_click { None } else { Some((index, now)) };
+
+                self.move_to(index);
+                // The checkbox follows the two-character cursor column.
+                let checkbox_start = self.list_area.x + 1 + 2;
+                let on_checkbox = (checkbox_start..checkbox_start + 3).contains(&mouse.column);
+                if is_double_click {
+                    if self.current_entry().is_some_and(|e| e.is_dir) {
+                        self.enter_directory();
+                    } else {
+                        self.toggle_selection();
+                    }
+                } else if on_checkbox {
+                    self.toggle_selection();
+                }
             }
+            _ => {}
         }
     }
 
-    fn get_selected_paths(&self) -> Vec<PathBuf> {
-        self.selected_indices.iter()
-            .filter_map(|&i| self.items.get(i).cloned())
-            .collect()
+    /// The index into `visible` of the list row at a screen position.
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        // Rows start below the top border and the header.
+        let first_row = area.y + 2;
+        let inside = column > area.x && column + 1 < area.x + area.width
+            && row >= first_row && row + 1 < area.y + area.height;
+        if !inside {
+            return None;
+        }
+        let index = self.table_state.offset() + (row - first_row) as usize;
+        (index < self.visible.len()).then_some(index)
     }
-}
 
-pub fn run_tui_selection(start_path: &Path, config: &Config) -> Result<Vec<PathBuf>, String> {
+    /// Number of list rows that fit between the borders and below the header.
+    fn page_height(&self) -> usize {
+        self.terminal_height.saturating_sub(3).max(1)
+    }
 
-    let all_files = WalkDir::new(start_path)
-        .max_depth(1) 
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file() && e.path() != start_path) 
-        .map(|e| e.path().to_path_buf())
-        .collect::<Vec<PathBuf>>();
+    /// The index into `visible` of the highlighted row.
+    fn cursor(&self) -> usize {
+        self.table_state.selected().unwrap_or(0)
+    }
 
-    if all_files.is_empty() {
-        config.print_normal(&format!("No files found in '{}' for TUI selection.", start_path.display()));
-        return Ok(Vec::new());
+    /// Moves the cursor to `index`, clamped to the list. The table scrolls it
+    /// into view when it is drawn.
+    fn move_to(&mut self, index: usize) {
+        let selected = (!self.visible.is_empty()).then(|| index.min(self.visible.len() - 1));
+        self.table_state.select(selected);
+    }
+
+    fn move_up(&mut self) {
+        self.move_by(-1);
+    }
+
+    fn move_down(&mut self) {
+        self.move_by(1);
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        self.move_to(self.cursor().saturating_add_signed(delta));
     }
 
-    // Setup terminal
-    enable_raw_mode().map_err(|e| format!("Failed to enable raw mode: {}", e))?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen).map_err(|e| format!("Failed to enter alternate screen: {}", e))?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).map_err(|e| format!("Failed to create terminal: {}", e))?;
+    /// Loads the preview of the highlighted entry unless it is already shown.
+    fn refresh_preview(&mut self) {
+        if !self.show_preview {
+            return;
+        }
+        let Some(path) = self.current_entry().map(|e| e.path.clone()) else {
+            self.preview = None;
+            return;
+        };
+        if self.preview.as_ref().is_none_or(|p| p.path != path) {
+            self.preview = Some(Preview::load(&path, &self.theme));
+        }
+    }
 
-    let initial_height = terminal.size().map_err(|e| format!("Failed to get terminal size: {}", e))?.height;
-    let mut app_state = TuiState::new(all_files, initial_height);
+    fn get_selected_paths(&self) -> Vec<PathBuf> {
+        self.selected.paths().to_vec()
+    }
 
-    let result = run_app(&mut terminal, &mut app_state);
+    /// Paths added to and removed from the hold in this session.
+    fn changes(&self) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+        for (path, mark) in self.hold.entries(&self.selected) {
+            match mark {
+                HoldMark::Added => added.push(path),
+                HoldMark::Removed => removed.push(path),
+                HoldMark::Held => {}
+            }
+        }
+        (added, removed)
+    }
+}
 
-    // Restore terminal
-    disable_raw_mode().map_err(|e| format!("Failed to disable raw mode: {}", e))?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen).map_err(|e| format!("Failed to leave alternate screen: {}", e))?;
-    terminal.show_cursor().map_err(|e| format!("Failed to show cursor: {}", e))?;
+pub fn run_tui_selection(start_path: &Path, traversal: &TraversalArgs, config: &Config) -> Result<TuiOutcome, String> {
+    let start_path = start_path.canonicalize()
+        .map_err(|e| format!("Failed to resolve directory {}: {}", start_path.display(), e))?;
+    let lister = DirectoryLister::new(traversal)?;
+    let keymap = Keymap::new(&config.settings.tui.keymap, Screen::Select)?;
+    let theme = Theme::load(&config.settings.tui)?;
+    let held = selection_store::read_selected_paths(config.hold())?;
+    let initial_items = lister.list(&start_path)?;
 
-    match result {
-        Ok(_) => Ok(app_state.get_selected_paths()),
-        Err(e) => Err(e.to_string()),
+    if initial_items.is_empty() {
+        config.print_normal(&format!("No files found in '{}' for TUI selection.", start_path.display()));
+        return Ok(TuiOutcome::Cancelled);
     }
+    let hold = HoldPanel::new(config.hold().unwrap_or(selection_store::DEFAULT_HOLD_NAME), held);
+
+    let mut guard = TerminalGuard::enter()?;
+    let initial_height = guard.terminal().size()
+        .map_err(|e| format!("Failed to get terminal size: {}", e))?
+        .height;
+    let mut app_state = TuiState::new(lister, keymap, theme, hold, &start_path, initial_items, initial_height);
+
+    let result = run_app(guard.terminal(), &mut app_state);
+    let restored = guard.restore();
+    // An error from the TUI itself matters more than one from restoring the terminal.
+    let outcome = result.map_err(|e| format!("Interactive selection failed: {}", e))?;
+    restored?;
+    Ok(outcome)
 }
 
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app_state: &mut TuiState,
-) -> io::Result<()> {
+) -> io::Result<TuiOutcome> {
+    // Only input changes what is shown, so the screen is redrawn after events
+    // that may have changed something rather than on every mouse movement.
+    let mut redraw = true;
     loop {
-        terminal.draw(|f| {
-            let size = f.area();
-            app_state.terminal_height = size.height as usize;
-
-            let mut list_items: Vec<ListItem> = Vec::new();
-            let visible_height = app_state.terminal_height.saturating_sub(2);
-            let visible_start = app_state.scroll_offset;
-            let visible_end = (app_state.scroll_offset + visible_height).min(app_state.items.len());
-
-            for i in visible_start..visible_end {
-                if let Some(path) = app_state.items.get(i) {
-                    let mut _s = path.display().to_string();
-                    if let Some(stripped_path) = path.strip_prefix(app_state.items[0].parent().unwrap_or(path))
-                        .ok().and_then(|p| if p.as_os_str().is_empty() { None } else { Some(p) }) {
-                        _s = stripped_path.display().to_string();
-                    } else { 
-                        _s = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
-                    }
+        if redraw {
+            app_state.refresh_preview();
+            draw_app(terminal, app_state)?;
+        }
+        redraw = true;
 
-                    if app_state.selected_indices.contains(&i) {
-                        _s = format!("[x] {}", _s);
-                    } else {
-                        _s = format!("[ ] {}", _s);
-                    }
-                    if i == app_state.current_scroll_index {
-                        _s = format!("> {}", _s);
-                    } else {
-                        _s = format!("  {}", _s);
-                    }
-                    list_items.push(ListItem::new(_s));
+        // While sizes are measured in the background, wake up now and then
+        // to show the growing total.
+        if app_state.sizes.is_measuring() && !event::poll(SIZE_POLL_INTERVAL)? {
+            redraw = app_state.collect_sizes();
+            continue;
+        }
+        match event::read()? {
+            Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Moved | MouseEventKind::Drag(_) | MouseEventKind::Up(_)) => {
+                redraw = false;
+            }
+            Event::Mouse(mouse) => app_state.handle_mouse(mouse),
+            Event::Resize(_, rows) => {
+                // The table keeps the cursor in view by itself once redrawn;
+                // the help popup's scroll position is clamped to the new height.
+                terminal.autoresize()?;
+                app_state.terminal_height = rows.saturating_sub(1) as usize;
+                app_state.scroll_help(0);
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if let Some(outcome) = handle_key(app_state, key) {
+                    return Ok(outcome);
+                }
+            }
+            _ => redraw = false,
+        }
+    }
+}
+
+fn draw_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app_state: &mut TuiState) -> io::Result<()> {
+    terminal.draw(|f| {
+        let size = f.area();
+        let [main_area, help_area] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+            .areas(size);
+        app_state.terminal_height = main_area.height as usize;
+        let show_side_pane = app_state.hold.visible || app_state.show_preview;
+        let (list_area, side_area) = if show_side_pane && size.width >= MIN_WIDTH_FOR_PREVIEW {
+            let [list_area, side_area] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main_area);
+            (list_area, Some(side_area))
+        } else {
+            (main_area, None)
+        };
+
+        app_state.list_area = list_area;
+        let columns = table_columns(list_area.width.saturating_sub(2));
+        let mut rows: Vec<Row> = Vec::with_capacity(app_state.visible.len());
+
+        for (i, visible_item) in app_state.visible.iter().enumerate() {
+            let entry = &app_state.items[visible_item.index];
+            let checkbox = match app_state.selected.state(&entry.path) {
+                SelectionState::Selected => "[x] ",
+                SelectionState::Partial => "[-] ",
+                SelectionState::Unselected => "[ ] ",
+            };
+            let name_style = app_state.theme.entry_style(&entry.path, entry.is_dir, entry.metadata.as_ref());
+            let tree_prefix = match app_state.view {
+                ViewMode::Browser => String::new(),
+                ViewMode::Tree => {
+                    let marker = match (entry.is_dir, app_state.expanded.contains(&entry.path)) {
+                        (true, true) => "▾ ",
+                        (true, false) => "▸ ",
+                        (false, _) => "  ",
+                    };
+                    format!("{}{}", "  ".repeat(entry.depth), marker)
                 }
+            };
+
+            let mut spans = vec![Span::raw(format!("{}{}", checkbox, tree_prefix))];
+            spans.extend(label_spans(&app_state.label(entry), &visible_item.positions, name_style, app_state.theme.matched));
+            let cells = columns.iter().map(|column| match column {
+                SortKey::Name => Cell::from(Line::from(spans.clone())),
+                SortKey::Size => Cell::from(Line::from(entry.size().map(format::human_size).unwrap_or_default()).right_aligned()),
+                SortKey::Modified => Cell::from(entry.modified().map(format::format_timestamp).unwrap_or_default()),
+                SortKey::Permissions => Cell::from(entry.metadata.as_ref().map(format::format_permissions).unwrap_or_default()),
+            });
+            let mut row = Row::new(cells);
+            if app_state.selected.state(&entry.path) == SelectionState::Selected {
+                row = row.style(app_state.theme.selected);
+            }
+            if app_state.in_visual_range(i) {
+                row = row.style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            rows.push(row);
+        }
+
+        let header = Row::new(columns.iter().map(|column| {
+            let mut label = sort_key_label(*column).to_string();
+            if *column == app_state.sort.key {
+                label.push_str(if app_state.sort.descending { " ▼" } else { " ▲" });
             }
+            Cell::from(label)
+        }))
+            .style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED));
+
+        let title = match app_state.input_mode {
+            InputMode::Pattern => format!("Select matching: {}_", app_state.pattern_input),
+            InputMode::Filter => format!(
+                "Filter: {}_ ({}/{})",
+                app_state.query, app_state.visible.len(), app_state.items.len()
+            ),
+            InputMode::Normal if !app_state.query.is_empty() => format!(
+                "Filter: {} ({}/{})",
+                app_state.query, app_state.visible.len(), app_state.items.len()
+            ),
+            InputMode::Normal => match app_state.view {
+                ViewMode::Browser => app_state.current_dir.display().to_string(),
+                ViewMode::Tree => format!("{} (tree)", app_state.root.display()),
+            },
+        };
+        let status = match &app_state.message {
+            Some(message) => message.clone(),
+            None => format!(
+                "{} selected ({})",
+                app_state.selected.len(), app_state.selected_size_label()
+            ),
+        };
+        let help = match app_state.input_mode {
+            InputMode::Filter => "Type to filter | Tab: toggle | Enter: keep filter | Esc: clear".to_string(),
+            InputMode::Pattern => "Glob pattern, e.g. *.rs | Enter: select matching | Esc: cancel".to_string(),
+            InputMode::Normal if app_state.hold.visible => format!(
+                "Hold: {}: remove/restore | {}: close panel | {}: confirm",
+                app_state.keymap.keys_for(Command::Toggle),
+                app_state.keymap.keys_for(Command::HoldPanel),
+                app_state.keymap.keys_for(Command::Confirm)
+            ),
+            InputMode::Normal if app_state.visual_anchor.is_some() => format!(
+                "Range: move to extend | {}: select range | Esc: cancel",
+                app_state.keymap.keys_for(Command::Range)
+            ),
+            InputMode::Normal => [
+                (Command::Toggle, "toggle"),
+                (Command::Filter, "filter"),
+                (Command::Confirm, "confirm"),
+                (Command::Quit, "quit"),
+                (Command::Help, "all keys"),
+            ]
+                .iter()
+                .map(|(c, label)| format!("{}: {}", app_state.keymap.keys_for(*c), label))
+                .collect::<Vec<_>>()
+                .join(" | "),
+        };
+
+        let widths = columns.iter().map(|column| match column {
+            SortKey::Name => Constraint::Min(NAME_COLUMN_MIN_WIDTH),
+            SortKey::Size => Constraint::Length(SIZE_COLUMN_WIDTH),
+            SortKey::Modified => Constraint::Length(MODIFIED_COLUMN_WIDTH),
+            SortKey::Permissions => Constraint::Length(PERMISSIONS_COLUMN_WIDTH),
+        });
+        let table_widget = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title).title_bottom(status))
+            .row_highlight_style(app_state.theme.cursor)
+            .highlight_symbol("> ")
+            .highlight_spacing(HighlightSpacing::Always);
+
+        f.render_stateful_widget(table_widget, list_area, &mut app_state.table_state);
+        render_scrollbar(f, list_area, app_state.visible.len(), app_state.cursor());
+
+        if app_state.hold.visible {
+            // The hold panel needs the room even in a narrow terminal.
+            let hold_area = side_area.unwrap_or(list_area);
+            app_state.hold_area = Some(hold_area);
+            render_hold_panel(f, app_state, hold_area);
+        } else if let (Some(area), Some(preview)) = (side_area, &app_state.preview) {
+            let name = preview.path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| preview.path.display().to_string());
+            let preview_widget = Paragraph::new(preview.lines.clone())
+                .block(Block::default().borders(Borders::ALL).title(format!("Preview: {} (p: hide)", name)));
+            f.render_widget(preview_widget, area);
+        }
+
+        f.render_widget(Paragraph::new(help).style(Style::default().add_modifier(Modifier::DIM)), help_area);
+
+        if app_state.show_help {
+            render_help(f, &app_state.keymap, app_state.help_scroll, size);
+        } else if app_state.confirming {
+            render_summary(f, app_state, size);
+        }
+    })?;
+    Ok(())
+}
 
-            let list_widget = List::new(list_items)
-                .block(Block::default().borders(Borders::ALL).title("Select Files (Space: toggle, Enter: confirm, q: quit)"))
-                .highlight_style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+/// Ctrl-C, which leaves a text input like Esc does.
+fn is_interrupt(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
 
-            f.render_widget(list_widget, size);
-        })?;
+/// Keys held with Ctrl or Alt are not text, so they are not typed into inputs.
+fn has_command_modifier(key: KeyEvent) -> bool {
+    key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
 
-        if let Event::Key(key) = event::read()? {
+/// Applies a key press, returning the outcome once the user confirms or quits.
+fn handle_key(app_state: &mut TuiState, key: KeyEvent) -> Option<TuiOutcome> {
+    match app_state.input_mode {
+        InputMode::Filter => {
             match key.code {
-                KeyCode::Char('q') | KeyCode::Esc => {
-                    break;
+                _ if is_interrupt(key) => {
+                    app_state.clear_query();
+                    app_state.input_mode = InputMode::Normal;
+                }
+                KeyCode::Esc => {
+                    app_state.clear_query();
+                    app_state.input_mode = InputMode::Normal;
+                }
+                KeyCode::Enter => {
+                    app_state.input_mode = InputMode::Normal;
+                }
+                KeyCode::Backspace => {
+                    app_state.pop_query_char();
                 }
                 KeyCode::Up => {
                     app_state.move_up();
@@ -165,15 +895,329 @@ fn run_app<B: ratatui::backend::Backend>(
                 KeyCode::Down => {
                     app_state.move_down();
                 }
-                KeyCode::Char(' ') => {
+                KeyCode::Tab => {
                     app_state.toggle_selection();
+                    app_state.move_down();
+                }
+                KeyCode::Char(c) if !has_command_modifier(key) => {
+                    app_state.push_query_char(c);
+                }
+                _ => {}
+            }
+            return None;
+        }
+        InputMode::Pattern => {
+            match key.code {
+                _ if is_interrupt(key) => {
+                    app_state.pattern_input.clear();
+                    app_state.input_mode = InputMode::Normal;
+                }
+                KeyCode::Esc => {
+                    app_state.pattern_input.clear();
+                    app_state.input_mode = InputMode::Normal;
                 }
                 KeyCode::Enter => {
-                    break;
+                    app_state.select_matching_pattern();
+                    app_state.input_mode = InputMode::Normal;
+                }
+                KeyCode::Backspace => {
+                    app_state.pattern_input.pop();
+                }
+                KeyCode::Char(c) if !has_command_modifier(key) => {
+                    app_state.pattern_input.push(c);
                 }
                 _ => {}
             }
+            return None;
         }
+        InputMode::Normal => {}
     }
-    Ok(())
-}
\ No newline at end of file
+    app_state.message = None;
+    if app_state.confirming {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => return Some(TuiOutcome::Confirmed(app_state.get_selected_paths())),
+            KeyCode::Char('n') | KeyCode::Esc => app_state.confirming = false,
+            _ => {}
+        }
+        return None;
+    }
+    if app_state.show_help {
+        match app_state.keymap.lookup(key) {
+            Some(Command::Up) => app_state.scroll_help(-1),
+            Some(Command::Down) => app_state.scroll_help(1),
+            _ => app_state.show_help = false,
+        }
+        return None;
+    }
+    if app_state.hold.visible {
+        let len = app_state.hold.entries(&app_state.selected).len();
+        match app_state.keymap.lookup(key) {
+            Some(Command::Up) => app_state.hold.move_by(-1, len),
+            Some(Command::Down) => app_state.hold.move_by(1, len),
+            Some(Command::Toggle) => app_state.toggle_hold_entry(),
+            Some(Command::Help) => app_state.show_help = true,
+            Some(Command::Confirm) => app_state.confirming = true,
+            Some(Command::HoldPanel | Command::Quit) => app_state.hold.visible = false,
+            _ => {}
+        }
+        return None;
+    }
+    if key.code == KeyCode::Esc && app_state.visual_anchor.is_some() {
+        app_state.visual_anchor = None;
+        return None;
+    }
+    if key.code == KeyCode::Esc && !app_state.query.is_empty() {
+        app_state.clear_query();
+        return None;
+    }
+    let command = app_state.keymap.lookup(key)?;
+    let page_height = app_state.page_height() as isize;
+    match command {
+        Command::Up => app_state.move_up(),
+        Command::Down => app_state.move_down(),
+        Command::HalfPageUp => app_state.move_by(-page_height / 2),
+        Command::HalfPageDown => app_state.move_by(page_height / 2),
+        Command::PageUp => app_state.move_by(-page_height),
+        Command::PageDown => app_state.move_by(page_height),
+        Command::Top => app_state.move_to(0),
+        Command::Bottom => app_state.move_to(usize::MAX),
+        Command::Open => app_state.enter_directory(),
+        Command::Parent => app_state.leave_directory(),
+        Command::Toggle if app_state.visual_anchor.is_some() => app_state.toggle_visual_range(),
+        Command::Toggle => app_state.toggle_selection(),
+        Command::SelectAll => app_state.update_all_visible(SelectionChange::Select),
+        Command::SelectNone => app_state.update_all_visible(SelectionChange::Deselect),
+        Command::Invert => app_state.update_all_visible(SelectionChange::Toggle),
+        Command::Range => app_state.toggle_visual_range(),
+        Command::RangeUp | Command::RangeDown => {
+            if app_state.visual_anchor.is_none() {
+                app_state.visual_anchor = Some(app_state.cursor());
+            }
+            app_state.move_by(if command == Command::RangeUp { -1 } else { 1 });
+        }
+        Command::SelectMatching => app_state.input_mode = InputMode::Pattern,
+        Command::Filter => app_state.input_mode = InputMode::Filter,
+        Command::ToggleView => app_state.toggle_view(),
+        Command::TogglePreview => app_state.show_preview = !app_state.show_preview,
+        Command::Sort => {
+            app_state.sort.key = app_state.sort.key.next();
+            app_state.resort();
+        }
+        Command::SortReverse => {
+            app_state.sort.descending = !app_state.sort.descending;
+            app_state.resort();
+        }
+        Command::DirectoriesFirst => {
+            app_state.sort.directories_first = !app_state.sort.directories_first;
+            app_state.resort();
+        }
+        Command::HoldPanel => {
+            app_state.hold.visible = true;
+            app_state.hold.cursor = 0;
+        }
+        Command::Help => {
+            app_state.show_help = true;
+            app_state.help_scroll = 0;
+        }
+        Command::Confirm => app_state.confirming = true,
+        Command::Quit => return Some(TuiOutcome::Cancelled),
+        // Bound only in the drop browser and the selection manager.
+        Command::SwitchMode | Command::CopyTo | Command::MoveTo | Command::Remove | Command::Reveal => {}
+    }
+    None
+}
+
+/// Draws everything in the hold, marking what was added or removed in this session.
+fn render_hold_panel(f: &mut ratatui::Frame, app_state: &TuiState, area: Rect) {
+    let entries = app_state.hold.entries(&app_state.selected);
+    let (mut added, mut removed) = (0, 0);
+    let items: Vec<ListItem> = entries.iter()
+        .map(|(path, mark)| {
+            let shown = match path.strip_prefix(&app_state.root) {
+                Ok(relative) if !relative.as_os_str().is_empty() => relative.display().to_string(),
+                _ => path.display().to_string(),
+            };
+            let line = match mark {
+                HoldMark::Held => Line::raw(format!("  {}", shown)),
+                HoldMark::Added => {
+                    added += 1;
+                    Line::styled(format!("+ {}", shown), app_state.theme.color(Style::default().fg(Color::Green)))
+                }
+                HoldMark::Removed => {
+                    removed += 1;
+                    Line::styled(
+                        format!("- {}", shown),
+                        app_state.theme.color(Style::default().fg(Color::Red).add_modifier(Modifier::CROSSED_OUT)),
+                    )
+                }
+            };
+            ListItem::new(line)
+        })
+        .collect();
+
+    let title = format!(
+        "Hold: {} ({} selected, +{} -{})",
+        app_state.hold.name, app_state.selected.len(), added, removed
+    );
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_symbol("> ")
+        .highlight_style(app_state.theme.cursor);
+    let mut state = ListState::default().with_selected(Some(app_state.hold.cursor));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// Draws the summary of what confirming will save, with the changes of this session.
+fn render_summary(f: &mut ratatui::Frame, app_state: &TuiState, area: Rect) {
+    /// Changed paths listed per kind before the rest is only counted.
+    const MAX_LISTED: usize = 8;
+
+    let (added, removed) = app_state.changes();
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::styled(
+            format!(
+                "Save {} items ({}) to '{}'?",
+                app_state.selected.len(), app_state.selected_size_label(), app_state.hold.name
+            ),
+            bold,
+        ),
+        Line::raw(""),
+    ];
+    if added.is_empty() && removed.is_empty() {
+        lines.push(Line::raw("No changes to the stored selection."));
+    }
+    for (paths, sign, color) in [(&added, '+', Color::Green), (&removed, '-', Color::Red)] {
+        if paths.is_empty() {
+            continue;
+        }
+        let kind = if sign == '+' { "Added" } else { "Removed" };
+        lines.push(Line::styled(format!("{} ({})", kind, paths.len()), bold));
+        for path in paths.iter().take(MAX_LISTED) {
+            let shown = path.strip_prefix(&app_state.root).unwrap_or(path);
+            lines.push(Line::styled(format!("  {} {}", sign, shown.display()), app_state.theme.color(Style::default().fg(color))));
+        }
+        if paths.len() > MAX_LISTED {
+            lines.push(Line::raw(format!("  ... and {} more", paths.len() - MAX_LISTED)));
+        }
+    }
+
+    let width = lines.iter().map(|l| l.width() as u16).max().unwrap_or(0).max(40) + 4;
+    let popup = centered(area, width, lines.len() as u16 + 2);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Confirm (y/Enter: save, n/Esc: back)")),
+        popup,
+    );
+}
+
+/// A `width` x `height` rectangle in the middle of `area`, shrunk to fit.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Draws the key bindings of `keymap` in a box over the middle of `area`.
+fn render_help(f: &mut ratatui::Frame, keymap: &Keymap, scroll: u16, area: Rect) {
+    let rows: Vec<(String, &str)> = Command::ALL.iter()
+        .filter(|c| c.available_in(Screen::Select))
+        .map(|c| (keymap.keys_for(*c), c.description()))
+        .collect();
+    let key_width = rows.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+    let lines: Vec<Line> = rows.into_iter()
+        .map(|(keys, description)| Line::from(vec![
+            Span::styled(format!("{:<width$}  ", keys, width = key_width), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(description),
+        ]))
+        .collect();
+
+    let popup = centered(area, key_width as u16 + 50, lines.len() as u16 + 2);
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines)
+            .scroll((scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "Keys ({}/{}: scroll, any other key: close)",
+                keymap.keys_for(Command::Up), keymap.keys_for(Command::Down)
+            ))),
+        popup,
+    );
+}
+
+/// The columns that fit in `width`: the name always, then size, modification
+/// time and permissions while there is room.
+fn table_columns(width: u16) -> Vec<SortKey> {
+    let mut columns = vec![SortKey::Name];
+    let mut used = NAME_COLUMN_MIN_WIDTH;
+    for (column, column_width) in [
+        (SortKey::Size, SIZE_COLUMN_WIDTH),
+        (SortKey::Modified, MODIFIED_COLUMN_WIDTH),
+        (SortKey::Permissions, PERMISSIONS_COLUMN_WIDTH),
+    ] {
+        // One column of spacing separates adjacent columns.
+        if used + 1 + column_width > width {
+            break;
+        }
+        used += 1 + column_width;
+        columns.push(column);
+    }
+    columns
+}
+
+fn sort_key_label(key: SortKey) -> &'static str {
+    match key {
+        SortKey::Name => "Name",
+        SortKey::Size => "Size",
+        SortKey::Modified => "Modified",
+        SortKey::Permissions => "Permissions",
+    }
+}
+
+fn ordered(a: usize, b: usize) -> (usize, usize) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+
+/// Splits a label into runs of matched and unmatched characters, so a row
+/// needs a handful of spans rather than one per character.
+fn label_spans(label: &str, positions: &[usize], style: Style, matched: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (index, c) in label.chars().enumerate() {
+        let is_matched = positions.contains(&index);
+        if is_matched != run_matched && !run.is_empty() {
+            let run_style = if run_matched { style.patch(matched) } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_matched { style.patch(matched) } else { style }));
+    }
+    spans
+}
+
+/// Draws a scrollbar over the right border of a bordered table with a
+/// header, when its `len` rows do not fit.
+fn render_scrollbar(f: &mut ratatui::Frame, area: Rect, len: usize, position: usize) {
+    // Rows sit between the top border plus header and the bottom border.
+    let track = Rect { y: area.y + 2, height: area.height.saturating_sub(3), ..area };
+    if len <= track.height as usize {
+        return;
+    }
+    let mut state = ScrollbarState::new(len).position(position);
+    f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight).begin_symbol(None).end_symbol(None),
+        track,
+        &mut state,
+    );
+}
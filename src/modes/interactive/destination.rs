@@ -0,0 +1,363 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Gauge, List, ListItem, ListState, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use crate::config::Config;
+use crate::operations::{self, PlannedAction, PlannedTransfer, TransferMode};
+use crate::utils::terminal::{TerminalGuard, TuiTerminal};
+use super::keymap::{Command, Keymap, Screen};
+use super::theme::Theme;
+
+/// How the user left the drop TUI.
+pub enum DropOutcome {
+    /// Nothing was copied or moved.
+    Cancelled,
+    /// The plan was carried out. Each planned transfer comes with the error it
+    /// failed with, if any; skipped ones were not attempted.
+    Finished {
+        mode: TransferMode,
+        results: Vec<(PlannedTransfer, Option<String>)>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    /// Picking the destination directory.
+    Browse,
+    /// Looking over what will happen to each item.
+    Review,
+    /// Copying or moving, and afterwards showing how it went.
+    Run,
+}
+
+struct DropState {
+    keymap: Keymap,
+    theme: Theme,
+    sources: Vec<PathBuf>,
+    mode: TransferMode,
+    force: bool,
+    phase: Phase,
+    /// The directory being browsed.
+    dir: PathBuf,
+    /// `dir` itself, then its subdirectories.
+    subdirs: Vec<PathBuf>,
+    /// The directory picked for the plan.
+    destination: PathBuf,
+    dir_state: ListState,
+    plan: Vec<PlannedTransfer>,
+    plan_state: TableState,
+    /// One entry per planned transfer once it has been attempted.
+    results: Vec<Option<String>>,
+    message: Option<String>,
+}
+
+impl DropState {
+    fn change_directory(&mut self, dir: PathBuf, focus: Option<&Path>) {
+        match list_subdirectories(&dir) {
+            Ok(mut subdirs) => {
+                subdirs.insert(0, dir.clone());
+                let index = focus.and_then(|f| subdirs.iter().position(|d| d == f)).unwrap_or(0);
+                self.dir_state.select(Some(index));
+                self.subdirs = subdirs;
+                self.dir = dir;
+                self.message = None;
+            }
+            Err(e) => self.message = Some(e),
+        }
+    }
+
+    fn move_cursor(&mut self, command: Command, page_height: usize) {
+        let (state_index, len) = match self.phase {
+            Phase::Browse => (self.dir_state.selected(), self.subdirs.len()),
+            _ => (self.plan_state.selected(), self.plan.len()),
+        };
+        let Some(index) = state_index else {
+            return;
+        };
+        let page = page_height.max(1) as isize;
+        let target = match command {
+            Command::Up => index.saturating_sub(1),
+            Command::Down => index + 1,
+            Command::HalfPageUp => index.saturating_add_signed(-page / 2),
+            Command::HalfPageDown => index.saturating_add_signed(page / 2),
+            Command::PageUp => index.saturating_add_signed(-page),
+            Command::PageDown => index.saturating_add_signed(page),
+            Command::Top => 0,
+            Command::Bottom => usize::MAX,
+            _ => index,
+        }
+        .min(len.saturating_sub(1));
+        match self.phase {
+            Phase::Browse => self.dir_state.select(Some(target)),
+            _ => self.plan_state.select(Some(target)),
+        }
+    }
+
+    /// The highlighted directory: the browsed one itself or one of its subdirectories.
+    fn highlighted(&self) -> &Path {
+        self.dir_state.selected()
+            .and_then(|i| self.subdirs.get(i))
+            .unwrap_or(&self.dir)
+    }
+
+    fn review(&mut self) {
+        self.destination = self.highlighted().to_path_buf();
+        self.plan = operations::plan_transfer(&self.sources, &self.destination, self.force);
+        self.plan_state.select(Some(0));
+        self.phase = Phase::Review;
+    }
+
+    fn counts(&self) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0);
+        for planned in &self.plan {
+            match planned.action {
+                PlannedAction::Create => counts.0 += 1,
+                PlannedAction::Overwrite => counts.1 += 1,
+                PlannedAction::Skip(_) => counts.2 += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// Lets the user pick a directory to drop `sources` into, starting at
+/// `start_path`, review the plan and carry it out.
+pub fn run_drop_tui(
+    sources: &[PathBuf],
+    start_path: &Path,
+    mode: TransferMode,
+    force: bool,
+    config: &Config,
+) -> Result<DropOutcome, String> {
+    let start_path = start_path.canonicalize()
+        .map_err(|e| format!("Failed to resolve directory {}: {}", start_path.display(), e))?;
+    let mut state = DropState {
+        keymap: Keymap::new(&config.settings.tui.keymap, Screen::Drop)?,
+        theme: Theme::load(&config.settings.tui)?,
+        sources: sources.to_vec(),
+        mode,
+        force,
+        phase: Phase::Browse,
+        dir: start_path.clone(),
+        subdirs: Vec::new(),
+        destination: start_path.clone(),
+        dir_state: ListState::default(),
+        plan: Vec::new(),
+        plan_state: TableState::default(),
+        results: Vec::new(),
+        message: None,
+    };
+    state.change_directory(start_path, None);
+
+    let mut guard = TerminalGuard::enter()?;
+    let result = run_drop_app(guard.terminal(), &mut state);
+    let restored = guard.restore();
+    let outcome = result.map_err(|e| format!("Interactive drop failed: {}", e))?;
+    restored?;
+    Ok(outcome)
+}
+
+fn run_drop_app(terminal: &mut TuiTerminal, state: &mut DropState) -> io::Result<DropOutcome> {
+    loop {
+        let mut page_height = 0;
+        terminal.draw(|f| page_height = draw(f, state))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if state.phase == Phase::Run {
+            // The run is over once the results are drawn; any key leaves.
+            let results = state.plan.iter().cloned().zip(state.results.drain(..)).collect();
+            return Ok(DropOutcome::Finished { mode: state.mode, results });
+        }
+        let Some(command) = state.keymap.lookup(key) else {
+            continue;
+        };
+        match (state.phase, command) {
+            (_, Command::SwitchMode) => state.mode = state.mode.toggled(),
+            (Phase::Browse, Command::Quit) => return Ok(DropOutcome::Cancelled),
+            (Phase::Browse, Command::Open) => {
+                let dir = state.highlighted().to_path_buf();
+                if dir != state.dir {
+                    state.change_directory(dir, None);
+                }
+            }
+            (Phase::Browse, Command::Parent) => {
+                if let Some(parent) = state.dir.parent().map(Path::to_path_buf) {
+                    let previous = state.dir.clone();
+                    state.change_directory(parent, Some(&previous));
+                }
+            }
+            (Phase::Browse, Command::Confirm) => state.review(),
+            (Phase::Review, Command::Toggle) => {
+                if let Some(planned) = state.plan_state.selected().and_then(|i| state.plan.get_mut(i)) {
+                    planned.toggle_overwrite();
+                }
+            }
+            (Phase::Review, Command::Parent | Command::Quit) => state.phase = Phase::Browse,
+            (Phase::Review, Command::Confirm) => {
+                run_plan(terminal, state)?;
+            }
+            _ => state.move_cursor(command, page_height),
+        }
+    }
+}
+
+/// Carries out the plan one item at a time, redrawing after each so the
+/// progress bar and the per-item results stay current.
+fn run_plan(terminal: &mut TuiTerminal, state: &mut DropState) -> io::Result<()> {
+    state.phase = Phase::Run;
+    state.results.clear();
+    for index in 0..state.plan.len() {
+        state.plan_state.select(Some(index));
+        terminal.draw(|f| {
+            draw(f, state);
+        })?;
+        let planned = &state.plan[index];
+        let error = match planned.action {
+            PlannedAction::Skip(_) => None,
+            _ => operations::transfer(state.mode, &planned.source, &planned.destination).err(),
+        };
+        state.results.push(error);
+    }
+    // Keys pressed while the items were transferred would otherwise count
+    // as the key that closes the results before they were ever seen.
+    while event::poll(Duration::ZERO)? {
+        event::read()?;
+    }
+    Ok(())
+}
+
+/// Draws the current phase and returns the number of rows its list shows.
+fn draw(f: &mut Frame, state: &mut DropState) -> usize {
+    let [header_area, main_area, footer_area] = Layout::vertical([
+        Constraint::Length(if state.phase == Phase::Run { 3 } else { 1 }),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+        .areas(f.area());
+    let page_height = main_area.height.saturating_sub(3) as usize;
+
+    let destination = match state.phase {
+        Phase::Browse => state.highlighted(),
+        _ => &state.destination,
+    };
+    let header_text = format!(
+        "Drop {} items ({}) into {}",
+        state.sources.len(), state.mode.verb(), destination.display()
+    );
+    match state.phase {
+        Phase::Run => {
+            let done = state.results.len();
+            let total = state.plan.len().max(1);
+            let label = if done == state.plan.len() {
+                format!("Done: {}/{}", done, state.plan.len())
+            } else {
+                let doing = match state.mode {
+                    TransferMode::Copy => "Copying",
+                    TransferMode::Move => "Moving",
+                };
+                format!("{} {}/{}", doing, done + 1, state.plan.len())
+            };
+            let gauge = Gauge::default()
+                .block(Block::default().borders(Borders::ALL).title(header_text))
+                .gauge_style(state.theme.color(Style::default().fg(Color::Green)))
+                .ratio(done as f64 / total as f64)
+                .label(label);
+            f.render_widget(gauge, header_area);
+        }
+        _ => f.render_widget(
+            Paragraph::new(header_text).style(Style::default().add_modifier(Modifier::BOLD)),
+            header_area,
+        ),
+    }
+
+    match state.phase {
+        Phase::Browse => {
+            let items: Vec<ListItem> = state.subdirs.iter()
+                .map(|dir| {
+                    if *dir == state.dir {
+                        return ListItem::new(Line::styled("./ (this directory)", Style::default().add_modifier(Modifier::ITALIC)));
+                    }
+                    let name = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                    ListItem::new(Line::styled(format!("{}/", name), state.theme.directory))
+                })
+                .collect();
+            let title = match &state.message {
+                Some(message) => message.clone(),
+                None => format!("{} subdirectories", items.len() - 1),
+            };
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(state.dir.display().to_string()).title_bottom(title))
+                .highlight_symbol("> ")
+                .highlight_style(state.theme.cursor);
+            f.render_stateful_widget(list, main_area, &mut state.dir_state);
+        }
+        Phase::Review | Phase::Run => {
+            let rows: Vec<Row> = state.plan.iter().enumerate()
+                .map(|(index, planned)| {
+                    let (label, style) = match (&planned.action, state.results.get(index)) {
+                        (PlannedAction::Skip(reason), _) => (format!("skip: {}", reason), state.theme.color(Style::default().fg(Color::DarkGray))),
+                        (_, Some(Some(e))) => (format!("failed: {}", e), state.theme.error),
+                        (_, Some(None)) => ("done".to_string(), state.theme.color(Style::default().fg(Color::Green))),
+                        (PlannedAction::Overwrite, None) => ("overwrite".to_string(), state.theme.color(Style::default().fg(Color::Yellow))),
+                        (PlannedAction::Create, None) => (state.mode.verb().to_string(), Style::default()),
+                    };
+                    let name = planned.source.file_name().unwrap_or(planned.source.as_os_str()).to_string_lossy().into_owned();
+                    Row::new(vec![
+                        Cell::from(label),
+                        Cell::from(name),
+                        Cell::from(planned.source.parent().map(|p| p.display().to_string()).unwrap_or_default()),
+                    ])
+                        .style(style)
+                })
+                .collect();
+            let (create, overwrite, skip) = state.counts();
+            let summary = format!("{} to {}, {} to overwrite, {} skipped", create, state.mode.verb(), overwrite, skip);
+            let table = Table::new(rows, [Constraint::Percentage(35), Constraint::Percentage(25), Constraint::Percentage(40)])
+                .header(Row::new(vec!["Action", "Item", "From"]).style(Style::default().add_modifier(Modifier::BOLD)))
+                .block(Block::default().borders(Borders::ALL).title("Plan").title_bottom(summary))
+                .row_highlight_style(state.theme.cursor);
+            f.render_stateful_widget(table, main_area, &mut state.plan_state);
+        }
+    }
+
+    let keys = |command| state.keymap.keys_for(command);
+    let footer = match state.phase {
+        Phase::Browse => format!(
+            "{}: open | {}: up | {}: drop into highlighted | {}: copy/move | {}: cancel",
+            keys(Command::Open), keys(Command::Parent), keys(Command::Confirm), keys(Command::SwitchMode), keys(Command::Quit)
+        ),
+        Phase::Review => format!(
+            "{}: overwrite/skip | {}: copy/move | {}: start | {}: back",
+            keys(Command::Toggle), keys(Command::SwitchMode), keys(Command::Confirm), keys(Command::Quit)
+        ),
+        Phase::Run if state.results.len() == state.plan.len() => "Press any key to exit".to_string(),
+        Phase::Run => String::new(),
+    };
+    f.render_widget(Paragraph::new(footer).style(Style::default().add_modifier(Modifier::DIM)), footer_area);
+    page_height
+}
+
+/// The directories inside `dir`, by name.
+fn list_subdirectories(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+    Ok(subdirs)
+}
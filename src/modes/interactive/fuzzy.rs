@@ -0,0 +1,167 @@
+//! fzf-style fuzzy matching for the interactive filter.
+//!
+//! A pattern matches a candidate when all of its characters appear in order.
+//! The shortest window containing the match is scored, rewarding characters at
+//! word boundaries and runs of consecutive characters and penalising gaps, so
+//! `rdme` ranks `README.md` above `src/render_mode.rs`.
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL_CASE: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR: i64 = 8;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Character (not byte) indices of the matched characters in the candidate.
+    pub positions: Vec<usize>,
+}
+
+/// Matches `pattern` against `candidate`. Matching ignores case unless the
+/// pattern contains an uppercase character ("smart case"). An empty pattern
+/// matches everything with a score of zero.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    if pattern.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+    }
+
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let normalize = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).map(normalize).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+    }
+    let original: Vec<char> = candidate.chars().collect();
+    let chars: Vec<char> = original.iter().map(|&c| normalize(c)).collect();
+
+    // Forward pass: find where the first complete match ends.
+    let mut pattern_index = 0;
+    let mut end = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if c == pattern[pattern_index] {
+            pattern_index += 1;
+            if pattern_index == pattern.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward pass: from that end, find the latest start, giving the shortest window.
+    let mut pattern_index = pattern.len();
+    let mut start = end;
+    for i in (0..=end).rev() {
+        if chars[i] == pattern[pattern_index - 1] {
+            pattern_index -= 1;
+            if pattern_index == 0 {
+                start = i;
+                break;
+            }
+        }
+    }
+
+    // Score the window, greedily taking each pattern character as early as possible.
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0;
+    let mut pattern_index = 0;
+    let mut previous_match: Option<usize> = None;
+    let mut consecutive = 0;
+    for (i, &c) in chars.iter().enumerate().take(end + 1).skip(start) {
+        if pattern_index == pattern.len() {
+            break;
+        }
+        if c != pattern[pattern_index] {
+            continue;
+        }
+
+        score += SCORE_MATCH + boundary_bonus(&original, i);
+        if i == 0 {
+            score += BONUS_FIRST_CHAR;
+        }
+        match previous_match {
+            Some(previous) if previous + 1 == i => {
+                consecutive += 1;
+                score += BONUS_CONSECUTIVE * consecutive;
+            }
+            Some(previous) => {
+                consecutive = 0;
+                let gap = (i - previous - 1) as i64;
+                score -= PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (gap - 1);
+            }
+            None => {}
+        }
+
+        positions.push(i);
+        previous_match = Some(i);
+        pattern_index += 1;
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+fn boundary_bonus(chars: &[char], index: usize) -> i64 {
+    let Some(&previous) = index.checked_sub(1).and_then(|i| chars.get(i)) else {
+        return BONUS_BOUNDARY;
+    };
+    let current = chars[index];
+    if matches!(previous, '/' | '\\' | '_' | '-' | '.' | ' ') {
+        BONUS_BOUNDARY
+    } else if previous.is_lowercase() && current.is_uppercase() {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, candidate: &str) -> i64 {
+        fuzzy_match(pattern, candidate).expect("pattern should match").score
+    }
+
+    #[test]
+    fn ranks_boundary_matches_first() {
+        assert!(score("rdme", "README.md") > score("rdme", "src/render_mode.rs"));
+    }
+
+    #[test]
+    fn ranks_consecutive_matches_above_scattered_ones() {
+        assert!(score("main", "main.rs") > score("main", "my_animation.rs"));
+    }
+
+    #[test]
+    fn reports_positions_of_the_shortest_window() {
+        let m = fuzzy_match("ab", "a_xab").unwrap();
+        assert_eq!(m.positions, vec![3, 4]);
+    }
+
+    #[test]
+    fn positions_count_characters_not_bytes() {
+        let m = fuzzy_match("é.t", "café.txt").unwrap();
+        assert_eq!(m.positions, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn requires_characters_in_order() {
+        assert!(fuzzy_match("ba", "ab").is_none());
+        assert!(fuzzy_match("abc", "ab").is_none());
+    }
+
+    #[test]
+    fn uses_smart_case() {
+        assert!(fuzzy_match("readme", "README.md").is_some());
+        assert!(fuzzy_match("Readme", "readme.md").is_none());
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        assert_eq!(fuzzy_match("", "anything"), Some(FuzzyMatch { score: 0, positions: Vec::new() }));
+        assert_eq!(fuzzy_match("  ", "anything").map(|m| m.score), Some(0));
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+use super::selection::{Selection, SelectionState};
+
+/// How an entry of the hold panel relates to what was stored before the TUI
+/// was opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HoldMark {
+    /// Stored before and still selected.
+    Held,
+    /// Selected in this session.
+    Added,
+    /// Stored before but deselected in this session; dropped on confirm.
+    Removed,
+}
+
+/// The side panel listing everything in the hold, across all directories.
+pub struct HoldPanel {
+    pub name: String,
+    /// The paths stored when the TUI was opened.
+    held: Vec<PathBuf>,
+    pub visible: bool,
+    pub cursor: usize,
+}
+
+impl HoldPanel {
+    pub fn new(name: &str, held: Vec<PathBuf>) -> Self {
+        HoldPanel { name: name.to_string(), held, visible: false, cursor: 0 }
+    }
+
+    pub fn held(&self) -> &[PathBuf] {
+        &self.held
+    }
+
+    /// The current selection plus the stored paths deselected since, sorted by path.
+    pub fn entries(&self, selection: &Selection) -> Vec<(PathBuf, HoldMark)> {
+        let mut entries: Vec<(PathBuf, HoldMark)> = selection.paths().iter()
+            .map(|p| {
+                let mark = if self.was_held(p) { HoldMark::Held } else { HoldMark::Added };
+                (p.clone(), mark)
+            })
+            .collect();
+        for path in &self.held {
+            if selection.state(path) != SelectionState::Selected {
+                entries.push((path.clone(), HoldMark::Removed));
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    /// Whether `path` was stored, directly or through a stored ancestor.
+    fn was_held(&self, path: &Path) -> bool {
+        self.held.iter().any(|h| path.starts_with(h))
+    }
+
+    pub fn move_by(&mut self, delta: isize, len: usize) {
+        self.cursor = self.cursor.saturating_add_signed(delta).min(len.saturating_sub(1));
+    }
+}
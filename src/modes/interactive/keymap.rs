@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something the user can do in the normal mode of the TUI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Up,
+    Down,
+    HalfPageUp,
+    HalfPageDown,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Open,
+    Parent,
+    Toggle,
+    SelectAll,
+    SelectNone,
+    Invert,
+    Range,
+    RangeUp,
+    RangeDown,
+    SelectMatching,
+    Filter,
+    ToggleView,
+    TogglePreview,
+    Sort,
+    SortReverse,
+    DirectoriesFirst,
+    HoldPanel,
+    Help,
+    Confirm,
+    Quit,
+    /// Switches a drop between copying and moving.
+    SwitchMode,
+    /// Drops the rest of the selection with the destination browser.
+    CopyTo,
+    MoveTo,
+    /// Marks the entry for removal from the stored selection.
+    Remove,
+    /// Opens the entry's directory in the system file manager.
+    Reveal,
+}
+
+/// The TUIs sharing the keymap. Each binds only the commands it handles, so
+/// one key can mean something different in each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Screen {
+    /// `ferry select -i`.
+    Select,
+    /// `ferry drop -i`.
+    Drop,
+    /// `ferry list -i`.
+    Manager,
+}
+
+impl Command {
+    pub const ALL: [Command; 33] = [
+        Command::Up,
+        Command::Down,
+        Command::HalfPageUp,
+        Command::HalfPageDown,
+        Command::PageUp,
+        Command::PageDown,
+        Command::Top,
+        Command::Bottom,
+        Command::Open,
+        Command::Parent,
+        Command::Toggle,
+        Command::SelectAll,
+        Command::SelectNone,
+        Command::Invert,
+        Command::Range,
+        Command::RangeUp,
+        Command::RangeDown,
+        Command::SelectMatching,
+        Command::Filter,
+        Command::ToggleView,
+        Command::TogglePreview,
+        Command::Sort,
+        Command::SortReverse,
+        Command::DirectoriesFirst,
+        Command::HoldPanel,
+        Command::Help,
+        Command::Confirm,
+        Command::Quit,
+        Command::SwitchMode,
+        Command::CopyTo,
+        Command::MoveTo,
+        Command::Remove,
+        Command::Reveal,
+    ];
+
+    /// The name used for the command in the `[tui.keymap]` config table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::Up => "up",
+            Command::Down => "down",
+            Command::HalfPageUp => "half_page_up",
+            Command::HalfPageDown => "half_page_down",
+            Command::PageUp => "page_up",
+            Command::PageDown => "page_down",
+            Command::Top => "top",
+            Command::Bottom => "bottom",
+            Command::Open => "open",
+            Command::Parent => "parent",
+            Command::Toggle => "toggle",
+            Command::SelectAll => "select_all",
+            Command::SelectNone => "select_none",
+            Command::Invert => "invert",
+            Command::Range => "range",
+            Command::RangeUp => "range_up",
+            Command::RangeDown => "range_down",
+            Command::SelectMatching => "select_matching",
+            Command::Filter => "filter",
+            Command::ToggleView => "toggle_view",
+            Command::TogglePreview => "toggle_preview",
+            Command::Sort => "sort",
+            Command::SortReverse => "sort_reverse",
+            Command::DirectoriesFirst => "directories_first",
+            Command::HoldPanel => "hold_panel",
+            Command::Help => "help",
+            Command::Confirm => "confirm",
+            Command::Quit => "quit",
+            Command::SwitchMode => "switch_mode",
+            Command::CopyTo => "copy_to",
+            Command::MoveTo => "move_to",
+            Command::Remove => "remove",
+            Command::Reveal => "reveal",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Command::Up => "Move up",
+            Command::Down => "Move down",
+            Command::HalfPageUp => "Move up half a page",
+            Command::HalfPageDown => "Move down half a page",
+            Command::PageUp => "Move up a page",
+            Command::PageDown => "Move down a page",
+            Command::Top => "Go to the first entry",
+            Command::Bottom => "Go to the last entry",
+            Command::Open => "Open or expand a directory",
+            Command::Parent => "Go to the parent directory or collapse",
+            Command::Toggle => "Toggle the entry (or select the range)",
+            Command::SelectAll => "Select all listed entries",
+            Command::SelectNone => "Deselect all listed entries",
+            Command::Invert => "Invert the selection of listed entries",
+            Command::Range => "Start or select a range",
+            Command::RangeUp => "Extend a range upwards",
+            Command::RangeDown => "Extend a range downwards",
+            Command::SelectMatching => "Select entries matching a glob",
+            Command::Filter => "Fuzzy filter the list",
+            Command::ToggleView => "Switch between browser and tree view",
+            Command::TogglePreview => "Show or hide the preview",
+            Command::Sort => "Sort by the next column",
+            Command::SortReverse => "Reverse the sort order",
+            Command::DirectoriesFirst => "Group directories first or not",
+            Command::HoldPanel => "Show the hold panel (everything selected)",
+            Command::Help => "Show or hide this help",
+            Command::Confirm => "Review and save the selection",
+            Command::Quit => "Quit without saving",
+            Command::SwitchMode => "Switch between copying and moving (ferry drop -i)",
+            Command::CopyTo => "Copy the selection somewhere (ferry list -i)",
+            Command::MoveTo => "Move the selection somewhere (ferry list -i)",
+            Command::Remove => "Remove the entry from the selection (ferry list -i)",
+            Command::Reveal => "Open the entry's directory (ferry list -i)",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Command::Up => &["up", "k"],
+            Command::Down => &["down", "j"],
+            Command::HalfPageUp => &["ctrl-u"],
+            Command::HalfPageDown => &["ctrl-d"],
+            Command::PageUp => &["pageup", "ctrl-b"],
+            Command::PageDown => &["pagedown", "ctrl-f"],
+            Command::Top => &["home", "g"],
+            Command::Bottom => &["end", "G"],
+            Command::Open => &["right", "l"],
+            Command::Parent => &["left", "h", "backspace"],
+            Command::Toggle => &["space"],
+            Command::SelectAll => &["a"],
+            Command::SelectNone => &["n"],
+            Command::Invert => &["i"],
+            Command::Range => &["V", "v"],
+            Command::RangeUp => &["shift-up", "K"],
+            Command::RangeDown => &["shift-down", "J"],
+            Command::SelectMatching => &["*"],
+            Command::Filter => &["/"],
+            Command::ToggleView => &["t"],
+            Command::TogglePreview => &["p"],
+            Command::Sort => &["s"],
+            Command::SortReverse => &["S"],
+            Command::DirectoriesFirst => &["d"],
+            Command::HoldPanel => &["H"],
+            Command::Help => &["?"],
+            Command::Confirm => &["enter"],
+            Command::Quit => &["q", "esc"],
+            Command::SwitchMode => &["m"],
+            Command::CopyTo => &["c"],
+            Command::MoveTo => &["m"],
+            Command::Remove => &["x", "delete"],
+            Command::Reveal => &["r"],
+        }
+    }
+
+    /// Whether the command does something in `screen`.
+    pub fn available_in(self, screen: Screen) -> bool {
+        match self {
+            Command::SwitchMode => screen == Screen::Drop,
+            Command::CopyTo | Command::MoveTo | Command::Remove | Command::Reveal => screen == Screen::Manager,
+            _ => true,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Command> {
+        Command::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+/// A key with its modifiers, written like `j`, `G`, `ctrl-d` or `shift-up`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid key '{}'", text);
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text;
+        // A trailing '-' is the minus key itself, not a separator.
+        while let Some((prefix, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match prefix.to_ascii_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "a" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f if f.starts_with('f') => f[1..].parse().map(KeyCode::F).map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            },
+        };
+        Ok(KeyBinding::new(code, modifiers))
+    }
+
+    /// Characters carry their case, so Shift is folded into them: `shift-g`
+    /// becomes `G`, which then matches however the terminal reports it.
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) => {
+                let c = if modifiers.contains(KeyModifiers::SHIFT) { uppercase(c) } else { c };
+                KeyBinding { code: KeyCode::Char(c), modifiers: modifiers - KeyModifiers::SHIFT }
+            }
+            _ => KeyBinding { code, modifiers },
+        }
+    }
+}
+
+/// `c` in upper case, or unchanged if that takes more than one character.
+fn uppercase(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+impl From<KeyEvent> for KeyBinding {
+    fn from(event: KeyEvent) -> Self {
+        KeyBinding::new(event.code, event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Delete"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Maps keys to the commands of one screen. Starts from the built-in
+/// bindings, which the `[tui.keymap]` table of the config file can replace
+/// per command.
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Command)>,
+}
+
+impl Keymap {
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>, screen: Screen) -> Result<Self, String> {
+        for name in overrides.keys() {
+            if Command::from_name(name).is_none() {
+                let known: Vec<&str> = Command::ALL.iter().map(|c| c.name()).collect();
+                return Err(format!("Unknown TUI action '{}' in keymap. Known actions: {}", name, known.join(", ")));
+            }
+        }
+
+        let mut bindings = Vec::new();
+        for command in Command::ALL.into_iter().filter(|c| c.available_in(screen)) {
+            match overrides.get(command.name()) {
+                Some(keys) => {
+                    for key in keys {
+                        let binding = KeyBinding::parse(key)
+                            .map_err(|e| format!("{} in keymap for '{}'", e, command.name()))?;
+                        bindings.push((binding, command));
+                    }
+                }
+                None => {
+                    for key in command.default_keys() {
+                        let binding = KeyBinding::parse(key).expect("default key bindings are valid");
+                        bindings.push((binding, command));
+                    }
+                }
+            }
+        }
+        // A key rebound in the config file takes precedence over a default use of it.
+        bindings.sort_by_key(|(_, command)| !overrides.contains_key(command.name()));
+        Ok(Keymap { bindings })
+    }
+
+    pub fn lookup(&self, event: KeyEvent) -> Option<Command> {
+        self.lookup_binding(KeyBinding::from(event))
+    }
+
+    /// The keys that trigger `command`, as shown in the help, e.g. `Down/j`.
+    /// Keys claimed by another command are left out.
+    pub fn keys_for(&self, command: Command) -> String {
+        let keys: Vec<String> = self.bindings.iter()
+            .filter(|(binding, c)| *c == command && self.lookup_binding(*binding) == Some(command))
+            .map(|(binding, _)| binding.to_string())
+            .collect();
+        if keys.is_empty() { "(unbound)".to_string() } else { keys.join("/") }
+    }
+
+    fn lookup_binding(&self, key: KeyBinding) -> Option<Command> {
+        self.bindings.iter().find(|(binding, _)| *binding == key).map(|(_, command)| *command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        KeyBinding { code, modifiers }
+    }
+
+    #[test]
+    fn parses_plain_and_named_keys() {
+        assert_eq!(KeyBinding::parse("j"), Ok(binding(KeyCode::Char('j'), KeyModifiers::NONE)));
+        assert_eq!(KeyBinding::parse("G"), Ok(binding(KeyCode::Char('G'), KeyModifiers::NONE)));
+        assert_eq!(KeyBinding::parse("space"), Ok(binding(KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert_eq!(KeyBinding::parse("PageDown"), Ok(binding(KeyCode::PageDown, KeyModifiers::NONE)));
+        assert_eq!(KeyBinding::parse("f5"), Ok(binding(KeyCode::F(5), KeyModifiers::NONE)));
+        assert_eq!(KeyBinding::parse("-"), Ok(binding(KeyCode::Char('-'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        assert_eq!(KeyBinding::parse("ctrl-d"), Ok(binding(KeyCode::Char('d'), KeyModifiers::CONTROL)));
+        assert_eq!(
+            KeyBinding::parse("C-a-x"),
+            Ok(binding(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT))
+        );
+        assert_eq!(KeyBinding::parse("shift-up"), Ok(binding(KeyCode::Up, KeyModifiers::SHIFT)));
+        assert_eq!(KeyBinding::parse("ctrl--"), Ok(binding(KeyCode::Char('-'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn shift_with_a_character_means_its_upper_case() {
+        assert_eq!(KeyBinding::parse("shift-a"), KeyBinding::parse("A"));
+        assert_eq!(
+            KeyBinding::parse("ctrl-shift-a"),
+            Ok(binding(KeyCode::Char('A'), KeyModifiers::CONTROL))
+        );
+        let event = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT);
+        assert_eq!(KeyBinding::from(event), binding(KeyCode::Char('A'), KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_modifiers() {
+        assert!(KeyBinding::parse("").is_err());
+        assert!(KeyBinding::parse("hyper-a").is_err());
+        assert!(KeyBinding::parse("pgdn").is_err());
+        assert!(KeyBinding::parse("fx").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use crate::cli::TraversalArgs;
+use crate::modes::explicit::{self, TraversalFilter};
+use crate::modes::filters::MetadataFilter;
+
+/// One row of the interactive file browser.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Nesting level below the tree root; always 0 in the directory browser.
+    pub depth: usize,
+    pub metadata: Option<Metadata>,
+}
+
+impl Entry {
+    pub fn size(&self) -> Option<u64> {
+        self.metadata.as_ref().filter(|_| !self.is_dir).map(Metadata::len)
+    }
+
+    pub fn modified(&self) -> Option<std::time::SystemTime> {
+        self.metadata.as_ref().and_then(|m| m.modified().ok())
+    }
+
+    #[cfg(unix)]
+    fn mode(&self) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+        self.metadata.as_ref().map_or(0, |m| m.permissions().mode() & 0o7777)
+    }
+
+    #[cfg(not(unix))]
+    fn mode(&self) -> u32 {
+        self.metadata.as_ref().map_or(0, |m| u32::from(!m.permissions().readonly()))
+    }
+}
+
+/// The column the TUI list is sorted by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Modified,
+    Permissions,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Size,
+            SortKey::Size => SortKey::Modified,
+            SortKey::Modified => SortKey::Permissions,
+            SortKey::Permissions => SortKey::Name,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+    /// Lists directories before files regardless of the key.
+    pub directories_first: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder { key: SortKey::Name, descending: false, directories_first: true }
+    }
+}
+
+impl SortOrder {
+    /// Sorts `entries`, falling back to the path for equal keys so the order is stable.
+    pub fn sort(&self, entries: &mut [Entry]) {
+        entries.sort_by(|a, b| {
+            let by_key = match self.key {
+                SortKey::Name => Ordering::Equal,
+                SortKey::Size => a.size().cmp(&b.size()),
+                SortKey::Modified => a.modified().cmp(&b.modified()),
+                SortKey::Permissions => a.mode().cmp(&b.mode()),
+            };
+            let ordering = by_key.then_with(|| a.path.cmp(&b.path));
+            let ordering = if self.descending { ordering.reverse() } else { ordering };
+            if self.directories_first {
+                b.is_dir.cmp(&a.is_dir).then(ordering)
+            } else {
+                ordering
+            }
+        });
+    }
+}
+
+/// Lists directories for the TUI with the same ignore rules, --include/--exclude
+/// patterns, metadata filters and depth limits as the other selection modes.
+///
+/// Directories are always listed so they can be entered; the include patterns
+/// and metadata filters only decide which files are shown.
+pub struct DirectoryLister {
+    traversal: TraversalArgs,
+    filter: TraversalFilter,
+    metadata_filter: MetadataFilter,
+}
+
+impl DirectoryLister {
+    pub fn new(traversal: &TraversalArgs) -> Result<Self, String> {
+        Ok(DirectoryLister {
+            traversal: traversal.clone(),
+            filter: TraversalFilter::from_args(traversal)?,
+            metadata_filter: MetadataFilter::from_args(&traversal.filters)?,
+        })
+    }
+
+    /// Lists the entries below `dir` (its direct children unless --max-depth
+    /// says otherwise) in the default `SortOrder`.
+    pub fn list(&self, dir: &Path) -> Result<Vec<Entry>, String> {
+        self.list_to_depth(dir, self.traversal.max_depth.or(Some(1)), self.traversal.min_depth)
+    }
+
+    /// Lists only the direct children of `dir`, as the tree view expands one
+    /// level at a time.
+    pub fn list_children(&self, dir: &Path) -> Result<Vec<Entry>, String> {
+        self.list_to_depth(dir, Some(1), 0)
+    }
+
+    fn list_to_depth(&self, dir: &Path, max_depth: Option<usize>, min_depth: usize) -> Result<Vec<Entry>, String> {
+        let mut entries = Vec::new();
+        let walker = explicit::filtered_walker(dir, &self.traversal)?
+            .max_depth(max_depth)
+            .build();
+
+        for entry_result in walker {
+            let entry = entry_result
+                .map_err(|e| format!("Failed to read directory {}: {}", dir.display(), e))?;
+            if entry.depth() == 0 || entry.depth() < min_depth {
+                continue;
+            }
+            let is_dir = entry.path().is_dir();
+            let relative_path = explicit::relative_to(entry.path(), dir);
+            let is_shown = is_dir
+                || (self.filter.is_included(relative_path) && self.metadata_filter.matches(&entry));
+            if !is_shown {
+                continue;
+            }
+            entries.push(Entry {
+                path: entry.path().to_path_buf(),
+                is_dir,
+                depth: 0,
+                metadata: entry.metadata().ok(),
+            });
+        }
+
+        SortOrder::default().sort(&mut entries);
+        Ok(entries)
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command as Process, Stdio};
+use std::time::SystemTime;
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+use crate::config::Config;
+use crate::operations::TransferMode;
+use crate::selection_store;
+use crate::utils::format;
+use crate::utils::terminal::{TerminalGuard, TuiTerminal};
+use super::keymap::{Command, Keymap, Screen};
+use super::theme::Theme;
+
+/// How the user left the selection manager.
+pub enum ManagerOutcome {
+    /// Leave the stored selection as it was.
+    Discarded,
+    /// Store `paths`, in this order, as the selection.
+    Saved(Vec<PathBuf>),
+    /// Drop `paths` with the destination browser, storing them as the
+    /// selection only if the drop goes ahead.
+    Drop(Vec<PathBuf>, TransferMode),
+}
+
+/// Whether a stored path still matches what was selected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Staleness {
+    Fresh,
+    /// Modified after the selection was last saved.
+    Changed,
+    Missing,
+}
+
+struct ManagedEntry {
+    path: PathBuf,
+    metadata: Option<Metadata>,
+    /// Marked for removal; dropped from the selection on save.
+    removed: bool,
+}
+
+impl ManagedEntry {
+    fn parent(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("/"))
+    }
+
+    fn staleness(&self, saved_at: Option<SystemTime>) -> Staleness {
+        let Some(metadata) = &self.metadata else {
+            return Staleness::Missing;
+        };
+        match (metadata.modified().ok(), saved_at) {
+            (Some(modified), Some(saved_at)) if modified > saved_at => Staleness::Changed,
+            _ => Staleness::Fresh,
+        }
+    }
+}
+
+/// A row of the table: a source directory heading its group, or an entry.
+enum DisplayRow {
+    Group(PathBuf),
+    Entry(usize),
+}
+
+struct ManagerState {
+    keymap: Keymap,
+    theme: Theme,
+    hold_name: String,
+    entries: Vec<ManagedEntry>,
+    /// When the selection file was last written, to spot changed entries.
+    saved_at: Option<SystemTime>,
+    /// The grouped table rows, built once by `build_rows`.
+    rows: Vec<DisplayRow>,
+    /// Entry indices in display order, and each entry's place in it.
+    order: Vec<usize>,
+    positions: Vec<usize>,
+    /// Index into `entries` of the highlighted entry.
+    cursor: usize,
+    table_state: TableState,
+    dirty: bool,
+    message: Option<String>,
+}
+
+impl ManagerState {
+    /// Groups the entries by source directory, groups in order of first
+    /// appearance and entries in selection order within them. Reordering
+    /// only swaps entries within a group, so this is done once.
+    fn build_rows(&mut self) {
+        let mut groups: Vec<(&Path, Vec<usize>)> = Vec::new();
+        let mut group_of: HashMap<&Path, usize> = HashMap::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let group = *group_of.entry(entry.parent()).or_insert_with(|| {
+                groups.push((entry.parent(), Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].1.push(index);
+        }
+
+        let mut rows = Vec::with_capacity(self.entries.len() + groups.len());
+        let mut order = Vec::with_capacity(self.entries.len());
+        for (dir, indices) in groups {
+            rows.push(DisplayRow::Group(dir.to_path_buf()));
+            rows.extend(indices.iter().map(|&index| DisplayRow::Entry(index)));
+            order.extend(indices);
+        }
+        let mut positions = vec![0; self.entries.len()];
+        for (position, &index) in order.iter().enumerate() {
+            positions[index] = position;
+        }
+        self.rows = rows;
+        self.order = order;
+        self.positions = positions;
+    }
+
+    fn move_cursor(&mut self, command: Command, page_height: usize) {
+        if self.order.is_empty() {
+            return;
+        }
+        let position = self.positions[self.cursor];
+        let page = page_height.max(1) as isize;
+        let target = match command {
+            Command::Up => position.saturating_sub(1),
+            Command::Down => position + 1,
+            Command::HalfPageUp => position.saturating_add_signed(-page / 2),
+            Command::HalfPageDown => position.saturating_add_signed(page / 2),
+            Command::PageUp => position.saturating_add_signed(-page),
+            Command::PageDown => position.saturating_add_signed(page),
+            Command::Top => 0,
+            Command::Bottom => usize::MAX,
+            _ => position,
+        };
+        self.cursor = self.order[target.min(self.order.len() - 1)];
+    }
+
+    /// Swaps the highlighted entry with its neighbour in the same group, so it
+    /// is copied or moved earlier or later. The rows stay valid: entries of a
+    /// group are listed in index order, and both indices belong to it.
+    fn move_entry(&mut self, up: bool) {
+        let position = self.positions[self.cursor];
+        let neighbour_position = if up { position.checked_sub(1) } else { Some(position + 1) };
+        let Some(&neighbour) = neighbour_position.and_then(|p| self.order.get(p)) else {
+            return;
+        };
+        if self.entries[neighbour].parent() != self.entries[self.cursor].parent() {
+            return;
+        }
+        self.entries.swap(self.cursor, neighbour);
+        self.cursor = neighbour;
+        self.dirty = true;
+    }
+
+    fn toggle_removed(&mut self) {
+        let entry = &mut self.entries[self.cursor];
+        entry.removed = !entry.removed;
+        self.dirty = true;
+    }
+
+    fn kept_paths(&self) -> Vec<PathBuf> {
+        self.entries.iter()
+            .filter(|e| !e.removed)
+            .map(|e| e.path.clone())
+            .collect()
+    }
+
+    /// Opens the highlighted entry's directory in the system file manager.
+    fn reveal(&mut self) {
+        let dir = self.entries[self.cursor].parent().to_path_buf();
+        self.message = Some(match open_in_file_manager(&dir) {
+            Ok(()) => format!("Opened {}", dir.display()),
+            Err(e) => e,
+        });
+    }
+}
+
+/// Shows the stored selection grouped by directory, for reviewing, pruning
+/// and reordering it.
+pub fn run_manager_tui(paths: &[PathBuf], config: &Config) -> Result<ManagerOutcome, String> {
+    let saved_at = selection_store::get_selection_file_path(config.hold())
+        .ok()
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|metadata| metadata.modified().ok());
+    let mut state = ManagerState {
+        keymap: Keymap::new(&config.settings.tui.keymap, Screen::Manager)?,
+        theme: Theme::load(&config.settings.tui)?,
+        hold_name: config.hold().unwrap_or(selection_store::DEFAULT_HOLD_NAME).to_string(),
+        entries: paths.iter()
+            .map(|path| ManagedEntry {
+                path: path.clone(),
+                metadata: fs::symlink_metadata(path).ok(),
+                removed: false,
+            })
+            .collect(),
+        saved_at,
+        rows: Vec::new(),
+        order: Vec::new(),
+        positions: Vec::new(),
+        cursor: 0,
+        table_state: TableState::default(),
+        dirty: false,
+        message: None,
+    };
+    state.build_rows();
+    state.cursor = state.order.first().copied().unwrap_or(0);
+
+    let mut guard = TerminalGuard::enter()?;
+    let result = run_manager_app(guard.terminal(), &mut state);
+    let restored = guard.restore();
+    let outcome = result.map_err(|e| format!("Selection manager failed: {}", e))?;
+    restored?;
+    Ok(outcome)
+}
+
+fn run_manager_app(terminal: &mut TuiTerminal, state: &mut ManagerState) -> io::Result<ManagerOutcome> {
+    loop {
+        let mut page_height = 0;
+        terminal.draw(|f| page_height = draw(f, state))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        state.message = None;
+        let Some(command) = state.keymap.lookup(key) else {
+            continue;
+        };
+        match command {
+            Command::Toggle | Command::Remove => state.toggle_removed(),
+            Command::RangeUp => state.move_entry(true),
+            Command::RangeDown => state.move_entry(false),
+            Command::Reveal => state.reveal(),
+            Command::CopyTo => return Ok(ManagerOutcome::Drop(state.kept_paths(), TransferMode::Copy)),
+            Command::MoveTo => return Ok(ManagerOutcome::Drop(state.kept_paths(), TransferMode::Move)),
+            Command::Confirm if state.dirty => return Ok(ManagerOutcome::Saved(state.kept_paths())),
+            Command::Confirm | Command::Quit => return Ok(ManagerOutcome::Discarded),
+            _ => state.move_cursor(command, page_height),
+        }
+    }
+}
+
+/// Draws the manager and returns the number of table rows shown.
+fn draw(f: &mut Frame, state: &mut ManagerState) -> usize {
+    let [main_area, footer_area] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)])
+        .areas(f.area());
+
+    let mut highlighted = 0;
+    let rows: Vec<Row> = state.rows.iter().enumerate()
+        .map(|(row_index, row)| match row {
+            DisplayRow::Group(dir) => Row::new(vec![Cell::from(format!("{}/", dir.display()))])
+                .style(state.theme.directory),
+            DisplayRow::Entry(index) => {
+                if *index == state.cursor {
+                    highlighted = row_index;
+                }
+                let entry = &state.entries[*index];
+                let name = entry.path.file_name().unwrap_or(entry.path.as_os_str()).to_string_lossy();
+                let is_dir = entry.metadata.as_ref().is_some_and(Metadata::is_dir);
+                let size = match &entry.metadata {
+                    Some(_) if is_dir => "-".to_string(),
+                    Some(metadata) => format::human_size(metadata.len()),
+                    None => String::new(),
+                };
+                let modified = entry.metadata.as_ref()
+                    .and_then(|m| m.modified().ok())
+                    .map(format::format_timestamp)
+                    .unwrap_or_default();
+                let (status, status_style) = match entry.staleness(state.saved_at) {
+                    Staleness::Fresh => ("", Style::default()),
+                    Staleness::Changed => ("changed", state.theme.color(Style::default().fg(Color::Yellow))),
+                    Staleness::Missing => ("missing", state.theme.error),
+                };
+                let mut style = Style::default();
+                if entry.removed {
+                    style = state.theme.color(style.fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT));
+                }
+                let name_style = state.theme.entry_style(&entry.path, is_dir, entry.metadata.as_ref());
+                Row::new(vec![
+                    Cell::from(Line::styled(format!("  {}{}", name, if is_dir { "/" } else { "" }), name_style)),
+                    Cell::from(size),
+                    Cell::from(modified),
+                    Cell::from(status).style(status_style),
+                ])
+                    .style(style)
+            }
+        })
+        .collect();
+    state.table_state.select((!state.entries.is_empty()).then_some(highlighted));
+
+    let removed = state.entries.iter().filter(|e| e.removed).count();
+    let title = format!(
+        "Selection '{}': {} items{}",
+        state.hold_name,
+        state.entries.len(),
+        if removed > 0 { format!(", {} to remove", removed) } else { String::new() }
+    );
+    let status = state.message.clone()
+        .unwrap_or_else(|| if state.dirty { "unsaved changes".to_string() } else { String::new() });
+    let keys = |command| state.keymap.keys_for(command);
+    let exit_hint = format!("{}: save | {}: discard", keys(Command::Confirm), keys(Command::Quit));
+    let table = Table::new(rows, [Constraint::Min(20), Constraint::Length(10), Constraint::Length(16), Constraint::Length(8)])
+        .header(Row::new(vec!["Name", "Size", "Modified", "Status"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(title).title_bottom(status)
+            .title_bottom(Line::from(exit_hint).right_aligned()))
+        .row_highlight_style(state.theme.cursor);
+    f.render_stateful_widget(table, main_area, &mut state.table_state);
+
+    let footer = format!(
+        "{}/{}: remove | {}, {}: reorder | {}: reveal directory | {}/{}: copy/move to...",
+        keys(Command::Toggle), keys(Command::Remove), keys(Command::RangeUp), keys(Command::RangeDown),
+        keys(Command::Reveal), keys(Command::CopyTo), keys(Command::MoveTo)
+    );
+    f.render_widget(Paragraph::new(footer).style(Style::default().add_modifier(Modifier::DIM)), footer_area);
+    main_area.height.saturating_sub(3) as usize
+}
+
+#[cfg(target_os = "macos")]
+const FILE_MANAGER_OPENER: &str = "open";
+#[cfg(target_os = "windows")]
+const FILE_MANAGER_OPENER: &str = "explorer";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const FILE_MANAGER_OPENER: &str = "xdg-open";
+
+fn open_in_file_manager(dir: &Path) -> Result<(), String> {
+    Process::new(FILE_MANAGER_OPENER)
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run {}: {}", FILE_MANAGER_OPENER, e))
+}
@@ -0,0 +1,191 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use crate::utils::format;
+use super::theme::Theme;
+
+/// How many bytes are read to decide whether a file is text and to preview it.
+const PREVIEW_BYTES: usize = 16 * 1024;
+/// How many directory entries are listed in a directory preview.
+const MAX_DIRECTORY_ENTRIES: usize = 200;
+
+/// The contents of the preview pane for one path.
+pub struct Preview {
+    pub path: PathBuf,
+    pub lines: Vec<Line<'static>>,
+}
+
+impl Preview {
+    pub fn load(path: &Path, theme: &Theme) -> Self {
+        let lines = match fs::symlink_metadata(path) {
+            Err(e) => vec![error_line(&format!("Cannot read {}: {}", path.display(), e), theme)],
+            Ok(_) if path.is_dir() => directory_lines(path, theme),
+            // Opening a FIFO waits for a writer and devices can be endless,
+            // so only regular files (or links to them) are read.
+            Ok(metadata) if !path.is_file() => metadata_lines(path, &metadata, None),
+            Ok(metadata) => match read_head(path) {
+                Ok(head) if !head.is_empty() && looks_like_text(&head) => text_lines(&head),
+                Ok(head) => metadata_lines(path, &metadata, Some(&head)),
+                Err(_) => metadata_lines(path, &metadata, None),
+            },
+        };
+        Preview { path: path.to_path_buf(), lines }
+    }
+}
+
+fn read_head(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(PREVIEW_BYTES);
+    File::open(path)?.take(PREVIEW_BYTES as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Treats data as text if it has no NUL bytes and decodes as UTF-8, allowing
+/// for a multi-byte character cut off at the end of the sample.
+fn looks_like_text(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && head.len() - e.valid_up_to() < 4,
+    }
+}
+
+fn text_lines(head: &[u8]) -> Vec<Line<'static>> {
+    String::from_utf8_lossy(head)
+        .lines()
+        .map(|line| Line::raw(printable(line)))
+        .collect()
+}
+
+/// Expands tabs and shows other control characters in caret notation (`^[`
+/// for ESC), so that they are not sent to the terminal.
+fn printable(line: &str) -> String {
+    let mut shown = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '\t' => shown.push_str("    "),
+            '\u{0}'..='\u{1f}' => {
+                shown.push('^');
+                shown.push((c as u8 + b'@') as char);
+            }
+            '\u{7f}' => shown.push_str("^?"),
+            c if c.is_control() => shown.push(char::REPLACEMENT_CHARACTER),
+            c => shown.push(c),
+        }
+    }
+    shown
+}
+
+fn directory_lines(path: &Path, theme: &Theme) -> Vec<Line<'static>> {
+    let read_dir = match fs::read_dir(path) {
+        Ok(read_dir) => read_dir,
+        Err(e) => return vec![error_line(&format!("Cannot list {}: {}", path.display(), e), theme)],
+    };
+    let mut names: Vec<(bool, String)> = read_dir
+        .filter_map(|e| e.ok())
+        .map(|e| (e.path().is_dir(), e.file_name().to_string_lossy().into_owned()))
+        .collect();
+    if names.is_empty() {
+        return vec![Line::styled("(empty directory)", Style::default().add_modifier(Modifier::ITALIC))];
+    }
+    names.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    let total = names.len();
+    let mut lines: Vec<Line<'static>> = names.into_iter()
+        .take(MAX_DIRECTORY_ENTRIES)
+        .map(|(is_dir, name)| if is_dir {
+            Line::styled(format!("{}/", name), theme.directory)
+        } else {
+            Line::raw(name)
+        })
+        .collect();
+    if total > MAX_DIRECTORY_ENTRIES {
+        lines.push(Line::raw(format!("... and {} more", total - MAX_DIRECTORY_ENTRIES)));
+    }
+    lines
+}
+
+fn metadata_lines(path: &Path, metadata: &fs::Metadata, head: Option<&[u8]>) -> Vec<Line<'static>> {
+    let field = |name: &str, value: String| Line::from(vec![
+        Span::styled(format!("{:<12}", name), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(value),
+    ]);
+    let modified = metadata.modified()
+        .map(format::format_timestamp)
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let mut lines = vec![
+        field("Size", format!("{} ({} bytes)", format::human_size(metadata.len()), metadata.len())),
+        field("Modified", modified),
+        field("Permissions", format::format_permissions(metadata)),
+        field("Type", guess_mime(path, head).to_string()),
+    ];
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(path)
+            .map(|t| t.display().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        lines.push(field("Link to", target));
+    }
+    lines
+}
+
+/// Guesses a MIME type from well-known file signatures, then from the extension.
+fn guess_mime(path: &Path, head: Option<&[u8]>) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"\x7fELF", "application/x-executable"),
+        (b"MZ", "application/vnd.microsoft.portable-executable"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+        (b"\x00asm", "application/wasm"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    ];
+    if let Some(head) = head
+        && let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    if head.is_some_and(|h| h.is_empty()) {
+        return "inode/x-empty";
+    }
+
+    let extension = path.extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "txt" | "md" | "rs" | "toml" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn error_line(message: &str, theme: &Theme) -> Line<'static> {
+    Line::styled(message.to_string(), theme.error)
+}
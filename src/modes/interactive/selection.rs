@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+
+/// The paths toggled in the TUI. A selected directory stands for its whole
+/// subtree, so its descendants are never stored alongside it.
+#[derive(Default)]
+pub struct Selection {
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionState {
+    Unselected,
+    /// Some, but not all, of a directory's contents are selected.
+    Partial,
+    Selected,
+}
+
+impl Selection {
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn state(&self, path: &Path) -> SelectionState {
+        if self.paths.iter().any(|p| path.starts_with(p)) {
+            SelectionState::Selected
+        } else if self.paths.iter().any(|p| p.starts_with(path)) {
+            SelectionState::Partial
+        } else {
+            SelectionState::Unselected
+        }
+    }
+
+    /// Selects `path`, replacing any selected descendants.
+    pub fn select(&mut self, path: &Path) {
+        if self.state(path) == SelectionState::Selected {
+            return;
+        }
+        self.paths.retain(|p| !p.starts_with(path));
+        self.paths.push(path.to_path_buf());
+    }
+
+    /// Deselects `path` and everything below it. If it was only selected through
+    /// a selected ancestor, that ancestor is split up: its other contents, listed
+    /// with `children`, stay selected.
+    pub fn deselect<F>(&mut self, path: &Path, children: F)
+    where
+        F: Fn(&Path) -> Vec<PathBuf>,
+    {
+        self.paths.retain(|p| !p.starts_with(path));
+        let Some(ancestor_index) = self.paths.iter().position(|p| path.starts_with(p)) else {
+            return;
+        };
+        let ancestor = self.paths.remove(ancestor_index);
+
+        let mut dir = ancestor;
+        while let Ok(rest) = path.strip_prefix(&dir) {
+            let Some(next_component) = rest.components().next() else {
+                break;
+            };
+            let next = dir.join(next_component);
+            for child in children(&dir) {
+                if child != next {
+                    self.paths.push(child);
+                }
+            }
+            if next == path {
+                break;
+            }
+            dir = next;
+        }
+    }
+
+    /// Flips every path in `paths`. A directory listed together with some of
+    /// its descendants is left to them, and every state is read before any
+    /// change, so inverting a parent and its children at once is consistent.
+    pub fn invert<F>(&mut self, paths: &[PathBuf], children: F)
+    where
+        F: Fn(&Path) -> Vec<PathBuf>,
+    {
+        let leaves: Vec<(&PathBuf, bool)> = paths.iter()
+            .filter(|path| !paths.iter().any(|other| other != *path && other.starts_with(path)))
+            .map(|path| (path, self.state(path) == SelectionState::Selected))
+            .collect();
+        for (path, was_selected) in leaves {
+            if was_selected {
+                self.deselect(path, &children);
+            } else {
+                self.select(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn children(dir: &Path) -> Vec<PathBuf> {
+        match dir.to_str() {
+            Some("p") => vec![PathBuf::from("p/a"), PathBuf::from("p/b")],
+            _ => Vec::new(),
+        }
+    }
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn deselecting_a_child_splits_up_its_selected_parent() {
+        let mut selection = Selection::default();
+        selection.select(Path::new("p"));
+        selection.deselect(Path::new("p/a"), children);
+        assert_eq!(selection.paths(), paths(&["p/b"]));
+        assert_eq!(selection.state(Path::new("p")), SelectionState::Partial);
+    }
+
+    #[test]
+    fn invert_flips_children_listed_with_their_parent() {
+        let mut selection = Selection::default();
+        selection.select(Path::new("p/a"));
+        selection.invert(&paths(&["p", "p/a", "p/b"]), children);
+        assert_eq!(selection.paths(), paths(&["p/b"]));
+    }
+
+    #[test]
+    fn invert_of_a_selected_parent_and_its_children_clears_them() {
+        let mut selection = Selection::default();
+        selection.select(Path::new("p"));
+        selection.invert(&paths(&["p", "p/a", "p/b"]), children);
+        assert!(selection.paths().is_empty());
+    }
+
+    #[test]
+    fn invert_of_a_single_path_toggles_it() {
+        let mut selection = Selection::default();
+        selection.invert(&paths(&["p"]), children);
+        assert_eq!(selection.state(Path::new("p/a")), SelectionState::Selected);
+        selection.invert(&paths(&["p"]), children);
+        assert_eq!(selection.state(Path::new("p")), SelectionState::Unselected);
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use crate::utils::fs_helpers;
+
+/// The disk usage of selected paths, measured once per path on a background
+/// thread so that selecting a large directory does not freeze the UI.
+pub struct SizeCache {
+    sizes: HashMap<PathBuf, u64>,
+    /// Paths sent to the thread whose size has not come back yet.
+    pending: HashSet<PathBuf>,
+    requests: Sender<PathBuf>,
+    results: Receiver<(PathBuf, u64)>,
+}
+
+impl SizeCache {
+    pub fn new() -> Self {
+        let (requests, request_rx) = mpsc::channel::<PathBuf>();
+        let (result_tx, results) = mpsc::channel();
+        // The thread ends once the cache, and with it `requests`, is dropped.
+        thread::spawn(move || {
+            for path in request_rx {
+                let size = fs_helpers::disk_usage(&path);
+                if result_tx.send((path, size)).is_err() {
+                    break;
+                }
+            }
+        });
+        SizeCache { sizes: HashMap::new(), pending: HashSet::new(), requests, results }
+    }
+
+    /// The total size of `paths` as far as it is known, and whether some of
+    /// them are still being measured. Unknown paths are queued.
+    pub fn total(&mut self, paths: &[PathBuf]) -> (u64, bool) {
+        let mut total = 0;
+        let mut incomplete = false;
+        for path in paths {
+            match self.sizes.get(path) {
+                Some(size) => total += size,
+                None => {
+                    incomplete = true;
+                    if self.pending.insert(path.clone()) {
+                        let _ = self.requests.send(path.clone());
+                    }
+                }
+            }
+        }
+        (total, incomplete)
+    }
+
+    pub fn is_measuring(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Stores the sizes measured since the last call; true if there were any.
+    pub fn collect(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok((path, size)) = self.results.try_recv() {
+            self.pending.remove(&path);
+            self.sizes.insert(path, size);
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::Path;
+use std::str::FromStr;
+use ratatui::style::{Color, Modifier, Style};
+use crate::settings::TuiSettings;
+
+/// The built-in themes, selected with `theme = "<name>"` under `[tui]`.
+pub const THEME_NAMES: [&str; 3] = ["default", "light", "mono"];
+
+/// The styles the TUIs draw with.
+///
+/// A theme starts from a built-in one, takes directory, symlink, executable
+/// and per-extension colors from `LS_COLORS` when it is set, and finally
+/// applies the `[tui.colors]` overrides from the config file. With `NO_COLOR`
+/// set the `mono` theme is used, `LS_COLORS` is ignored and overrides only
+/// add their modifiers.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// The highlighted row.
+    pub cursor: Style,
+    /// Rows that are selected.
+    pub selected: Style,
+    pub directory: Style,
+    pub symlink: Style,
+    pub executable: Style,
+    /// Characters matched by the filter.
+    pub matched: Style,
+    pub error: Style,
+    /// File styles by lowercase extension, from `LS_COLORS`.
+    extensions: HashMap<String, Style>,
+    /// `NO_COLOR` is set.
+    no_color: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            cursor: Style::default().fg(Color::Black).bg(Color::Cyan),
+            selected: Style::default().fg(Color::Magenta),
+            directory: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            symlink: Style::default().fg(Color::Cyan),
+            executable: Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            matched: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            error: Style::default().fg(Color::Red),
+            extensions: HashMap::new(),
+            no_color: false,
+        }
+    }
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Theme::default()),
+            "light" => Some(Theme {
+                cursor: Style::default().fg(Color::White).bg(Color::Blue),
+                selected: Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                directory: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+                symlink: Style::default().fg(Color::Magenta),
+                executable: Style::default().fg(Color::Green),
+                matched: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                error: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                extensions: HashMap::new(),
+                no_color: false,
+            }),
+            "mono" => Some(Theme {
+                cursor: Style::default().add_modifier(Modifier::REVERSED),
+                selected: Style::default().add_modifier(Modifier::BOLD),
+                directory: Style::default().add_modifier(Modifier::BOLD),
+                symlink: Style::default().add_modifier(Modifier::ITALIC),
+                executable: Style::default(),
+                matched: Style::default().add_modifier(Modifier::UNDERLINED),
+                error: Style::default().add_modifier(Modifier::BOLD),
+                extensions: HashMap::new(),
+                no_color: false,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Builds the theme from the config file and the environment.
+    pub fn load(settings: &TuiSettings) -> Result<Self, String> {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let name = settings.theme.as_deref().unwrap_or("default");
+        let mut theme = Theme::builtin(name).ok_or_else(|| format!(
+            "Unknown theme '{}' in the config file. Built-in themes: {}",
+            name, THEME_NAMES.join(", ")
+        ))?;
+        if no_color {
+            theme = Theme { no_color, ..Theme::builtin("mono").unwrap_or_default() };
+        } else if let Ok(ls_colors) = std::env::var("LS_COLORS") {
+            theme.apply_ls_colors(&ls_colors);
+        }
+
+        for (key, spec) in &settings.colors {
+            let style = theme.color(parse_style(spec)
+                .map_err(|e| format!("Invalid color for '{}' in the config file: {}", key, e))?);
+            let slot = match key.as_str() {
+                "cursor" => &mut theme.cursor,
+                "selected" => &mut theme.selected,
+                "directory" => &mut theme.directory,
+                "symlink" => &mut theme.symlink,
+                "executable" => &mut theme.executable,
+                "matched" => &mut theme.matched,
+                "error" => &mut theme.error,
+                _ => return Err(format!(
+                    "Unknown color '{}' in the config file. Colors: cursor, selected, directory, symlink, executable, matched, error",
+                    key
+                )),
+            };
+            // Without colors an override could leave e.g. the cursor invisible,
+            // so it only adds its modifiers to the `mono` style.
+            *slot = if no_color { slot.patch(style) } else { style };
+        }
+        Ok(theme)
+    }
+
+    /// `style` without its colors when `NO_COLOR` is set, for the fixed
+    /// colors that mark additions, removals and the like.
+    pub fn color(&self, mut style: Style) -> Style {
+        if self.no_color {
+            style.fg = None;
+            style.bg = None;
+        }
+        style
+    }
+
+    /// The style for an entry's name, by file type and then by extension.
+    pub fn entry_style(&self, path: &Path, is_dir: bool, metadata: Option<&Metadata>) -> Style {
+        if metadata.is_some_and(|m| m.file_type().is_symlink()) {
+            return self.symlink;
+        }
+        if is_dir {
+            return self.directory;
+        }
+        if metadata.is_some_and(is_executable) {
+            return self.executable;
+        }
+        path.extension()
+            .and_then(|ext| self.extensions.get(&ext.to_string_lossy().to_lowercase()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Takes the `di`, `ln`, `ex` and `*.ext` entries of an `LS_COLORS` value.
+    /// Entries that are not plain SGR codes are skipped.
+    fn apply_ls_colors(&mut self, ls_colors: &str) {
+        for entry in ls_colors.split(':') {
+            let Some((key, codes)) = entry.split_once('=') else {
+                continue;
+            };
+            let Some(style) = parse_sgr(codes) else {
+                continue;
+            };
+            match key {
+                "di" => self.directory = style,
+                "ln" => self.symlink = style,
+                "ex" => self.executable = style,
+                _ => {
+                    if let Some(ext) = key.strip_prefix("*.") {
+                        self.extensions.insert(ext.to_lowercase(), style);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &Metadata) -> bool {
+    false
+}
+
+/// Parses a style such as `"bold yellow"` or `"black on #00afff"`: color
+/// names, `#rrggbb` or 0-255 indices, an optional `on <color>` background and
+/// any of `bold`, `dim`, `italic`, `underlined`, `reversed` and `crossed_out`.
+fn parse_style(spec: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next() {
+        let word = word.to_lowercase();
+        let modifier = match word.as_str() {
+            "bold" => Some(Modifier::BOLD),
+            "dim" => Some(Modifier::DIM),
+            "italic" => Some(Modifier::ITALIC),
+            "underlined" | "underline" => Some(Modifier::UNDERLINED),
+            "reversed" | "reverse" => Some(Modifier::REVERSED),
+            "crossed_out" | "strikethrough" => Some(Modifier::CROSSED_OUT),
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            style = style.add_modifier(modifier);
+        } else if word == "on" {
+            let background = words.next().ok_or("expected a color after 'on'")?;
+            style = style.bg(parse_color(background)?);
+        } else {
+            style = style.fg(parse_color(&word)?);
+        }
+    }
+    Ok(style)
+}
+
+fn parse_color(word: &str) -> Result<Color, String> {
+    Color::from_str(word).map_err(|_| format!("unknown color '{}'", word))
+}
+
+/// Parses SGR parameters as used by `LS_COLORS`, e.g. `01;34` or `38;5;208`.
+fn parse_sgr(codes: &str) -> Option<Style> {
+    let codes: Vec<u8> = codes.split(';')
+        .map(|code| if code.is_empty() { Some(0) } else { code.parse().ok() })
+        .collect::<Option<_>>()?;
+    let mut style = Style::default();
+    let mut iter = codes.into_iter();
+    while let Some(code) = iter.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            38 => style.fg(extended_color(&mut iter)?),
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            48 => style.bg(extended_color(&mut iter)?),
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            _ => style,
+        };
+    }
+    Some(style)
+}
+
+/// Reads the rest of a `38;5;n` or `38;2;r;g;b` sequence.
+fn extended_color(iter: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match iter.next()? {
+        5 => Some(Color::Indexed(iter.next()?)),
+        2 => Some(Color::Rgb(iter.next()?, iter.next()?, iter.next()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sgr_reads_ls_colors_codes() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let cases = [
+            ("01;34", Some(bold.fg(Color::Indexed(4)))),
+            ("38;5;208", Some(Style::default().fg(Color::Indexed(208)))),
+            ("38;2;255;128;0", Some(Style::default().fg(Color::Rgb(255, 128, 0)))),
+            ("48;5;17", Some(Style::default().bg(Color::Indexed(17)))),
+            ("4;92;41", Some(Style::default().add_modifier(Modifier::UNDERLINED).fg(Color::Indexed(10)).bg(Color::Indexed(1)))),
+            ("", Some(Style::default())),
+            ("01;", Some(Style::default())),
+            ("abc", None),
+            ("01;x", None),
+            ("38;5", None),
+            ("38;2;1;2", None),
+            ("38;9;1", None),
+            ("300", None),
+        ];
+        for (codes, expected) in cases {
+            assert_eq!(parse_sgr(codes), expected, "{:?}", codes);
+        }
+    }
+
+    #[test]
+    fn parse_style_reads_words_and_colors() {
+        let cases = [
+            ("black on #00afff", Ok(Style::default().fg(Color::Black).bg(Color::Rgb(0x00, 0xaf, 0xff)))),
+            ("bold yellow", Ok(Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow))),
+            ("Underline Reverse", Ok(Style::default().add_modifier(Modifier::UNDERLINED | Modifier::REVERSED))),
+            ("on 208", Ok(Style::default().bg(Color::Indexed(208)))),
+            ("", Ok(Style::default())),
+            ("black on", Err("expected a color after 'on'".to_string())),
+            ("sparkly", Err("unknown color 'sparkly'".to_string())),
+        ];
+        for (spec, expected) in cases {
+            assert_eq!(parse_style(spec), expected, "{:?}", spec);
+        }
+    }
+}
@@ -1,18 +1,31 @@
 pub mod interactive;
 pub mod explicit;
+pub mod combine;
+pub mod filters;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use super::selection_store;
+use crate::cli::{SelectArgs, SelectionMode, TraversalArgs};
 use crate::config::Config;
+use crate::output::{Action, Record, RecordWriter, Status};
+
+pub fn handle_select_command(args: SelectArgs, config: &Config) -> Result<(), String> {
+    let SelectArgs { items, regex, match_on, glob, stdin, null_delimited, interactive, path, dry_run, mut traversal, combine } = args;
+    config.settings.select.apply_to(&mut traversal);
+
+    if let Some((operation, names)) = combine::requested_operation(&combine) {
+        config.print_normal(&format!("Combining selections {}", names.join(", ")));
+        let combined = combine::run_set_operation(operation, names, config)?;
+        if dry_run {
+            config.print_normal(&format!("Dry run: the selection would hold {} items:", combined.len()));
+            print_selected_paths(&combined, config);
+        } else {
+            selection_store::replace_selected_paths(config.hold(), &combined)?;
+            config.print_normal(&format!("Saved {} items to the selection.", combined.len()));
+        }
+        return emit_selection_records(&combined, dry_run, config);
+    }
 
-pub fn handle_select_command(
-    items: Vec<String>,
-    regex: Option<String>,
-    interactive: bool,
-    path: Option<String>,
-    dry_run: bool,
-    config: &Config,
-) -> Result<(), String> {
     let resolved_start_path = path.as_deref().unwrap_or(".").to_string();
     let start_path_buf = PathBuf::from(&resolved_start_path);
 
@@ -21,46 +34,108 @@ pub fn handle_select_command(
         return Err(format!("The specified --path '{}' is not a valid directory.", resolved_start_path));
     }
 
-    let selected_paths: Vec<PathBuf> = if interactive {
+    // The TUI starts from the stored selection, so its result replaces it.
+    let mut replaces_selection = false;
+    let selected_paths: Vec<PathBuf> = if stdin {
+        config.print_normal("Reading paths to select from standard input");
+        explicit::read_items_from_stdin(null_delimited, config)?
+    } else if interactive {
         if !items.is_empty() {
             config.print_error("Do not provide item paths directly when using --interactive. Use --path to specify a starting directory.");
             return Err("Do not provide item paths directly when using --interactive. Use --path to specify a starting directory.".to_string());
         }
         config.print_normal(&format!("Launching interactive TUI selection from {})", start_path_buf.display()));
-        interactive::run_tui_selection(&start_path_buf, config)?
+        replaces_selection = true;
+        let Some(paths) = run_tui(&start_path_buf, &traversal, config)? else {
+            return RecordWriter::new(config.format).finish();
+        };
+        paths
     } else if let Some(regex_pattern) = regex {
         if !items.is_empty() {
             config.print_error("Do not provide item paths directly when using --regex. Use --path to specify a starting directory for the search.");
             return Err("Do not provide item paths directly when using --regex. Use --path to specify a starting directory for the search.".to_string());
         }
         config.print_normal(&format!("Running REGEX selection for '{}' in {})", regex_pattern, start_path_buf.display()));
-        explicit::run_regex_selection(&start_path_buf, &regex_pattern, config)?
+        explicit::run_regex_selection(&start_path_buf, &regex_pattern, match_on, &traversal, config)?
+    } else if let Some(glob_pattern) = glob {
+        if !items.is_empty() {
+            config.print_error("Do not provide item paths directly when using --glob. Use --path to specify a starting directory for the search.");
+            return Err("Do not provide item paths directly when using --glob. Use --path to specify a starting directory for the search.".to_string());
+        }
+        config.print_normal(&format!("Running GLOB selection for '{}' in {})", glob_pattern, start_path_buf.display()));
+        explicit::run_glob_selection(&start_path_buf, &glob_pattern, &traversal, config)?
     } else {
         if path.is_some() {
-            config.print_error("The --path flag is not applicable when directly providing item paths. It is used with --regex or --interactive.");
-            return Err("The --path flag is not applicable when directly providing item paths. It is used with --regex or --interactive.".to_string());
+            config.print_error("The --path flag is not applicable when directly providing item paths. It is used with --regex, --glob or --interactive.");
+            return Err("The --path flag is not applicable when directly providing item paths. It is used with --regex, --glob or --interactive.".to_string());
         }
 
         if items.is_empty() {
-            interactive::run_tui_selection(&PathBuf::from("."), config)?
+            replaces_selection = true;
+            let Some(paths) = run_tui(&PathBuf::from("."), &traversal, config)? else {
+                return RecordWriter::new(config.format).finish();
+            };
+            paths
         } else {
             config.print_normal(&format!("Selected {} items directly)", items.len()));
             explicit::validate_and_canonicalize_items(&items, config)?
         }
     };
 
-    if selected_paths.is_empty() {
+    if replaces_selection {
+        if dry_run {
+            config.print_normal(&format!("Dry run: the selection would hold {} items:", selected_paths.len()));
+            print_selected_paths(&selected_paths, config);
+        } else {
+            selection_store::replace_selected_paths(config.hold(), &selected_paths)?;
+            if selected_paths.is_empty() {
+                config.print_normal("Cleared the selection.");
+            } else {
+                config.print_normal(&format!("Saved {} items to the selection.", selected_paths.len()));
+            }
+        }
+    } else if selected_paths.is_empty() {
         config.print_normal("No valid items found to select. Nothing saved.");
     } else {
         if dry_run {
             config.print_normal("Dry run: would select the following:");
-            for p in &selected_paths {
-                config.print_normal(&format!("  {}", p.display()));
-            }
+            print_selected_paths(&selected_paths, config);
+        } else if config.settings.select.mode == SelectionMode::Replace {
+            selection_store::replace_selected_paths(config.hold(), &selected_paths)?;
+            config.print_normal(&format!("Replaced the selection with {} items.", selected_paths.len()));
         } else {
-            selection_store::write_selected_paths(&selected_paths)?;
+            selection_store::write_selected_paths(config.hold(), &selected_paths)?;
             config.print_normal(&format!("Selected {} items and saved to selection file.", selected_paths.len()));
         }
     }
-    Ok(())
+    emit_selection_records(&selected_paths, dry_run, config)
+}
+
+/// Runs the TUI, returning `None` if the user cancelled.
+fn run_tui(start_path: &Path, traversal: &TraversalArgs, config: &Config) -> Result<Option<Vec<PathBuf>>, String> {
+    match interactive::run_tui_selection(start_path, traversal, config)? {
+        interactive::TuiOutcome::Confirmed(paths) => Ok(Some(paths)),
+        interactive::TuiOutcome::Cancelled => {
+            config.print_normal("Selection cancelled. Nothing saved.");
+            Ok(None)
+        }
+    }
+}
+
+fn print_selected_paths(paths: &[PathBuf], config: &Config) {
+    if config.is_machine_readable() {
+        return;
+    }
+    for p in paths {
+        config.print_normal(&format!("  {}", p.display()));
+    }
+}
+
+fn emit_selection_records(paths: &[PathBuf], dry_run: bool, config: &Config) -> Result<(), String> {
+    let status = if dry_run { Status::Planned } else { Status::Ok };
+    let mut writer = RecordWriter::new(config.format);
+    for p in paths {
+        writer.emit(Record::new(Action::Select, status, p))?;
+    }
+    writer.finish()
 }
\ No newline at end of file
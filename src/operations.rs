@@ -1,12 +1,43 @@
 use super::selection_store;
+use std::collections::HashSet;
 use std::fs;
+use std::path::{Path, PathBuf};
+use crate::cli::{ConflictArgs, ConflictStrategy};
 use crate::config::Config;
+use crate::settings::TransferSettings;
+use crate::utils::fs_helpers;
+use crate::modes::interactive::{self, DropOutcome, ManagerOutcome};
+use crate::output::{Action, Record, RecordWriter, Status};
 
-pub fn handle_copy_command(force: bool, config: &Config) -> Result<(), String> {
-    let paths = selection_store::read_selected_paths()?;
+/// How copy, move and drop treat existing destinations and the selection.
+#[derive(Clone, Copy, Debug)]
+pub struct TransferOptions {
+    pub on_conflict: ConflictStrategy,
+    /// Keep copied items selected.
+    pub keep_selection: bool,
+}
+
+impl TransferOptions {
+    /// Command-line flags, falling back to the config file.
+    pub fn resolve(conflict: &ConflictArgs, keep: bool, settings: &TransferSettings) -> Self {
+        let on_conflict = if conflict.force {
+            ConflictStrategy::Overwrite
+        } else {
+            conflict.on_conflict.unwrap_or(settings.on_conflict)
+        };
+        TransferOptions { on_conflict, keep_selection: keep || settings.keep_selection }
+    }
+
+    pub fn from_settings(settings: &TransferSettings) -> Self {
+        TransferOptions { on_conflict: settings.on_conflict, keep_selection: settings.keep_selection }
+    }
+}
+
+pub fn handle_copy_command(options: TransferOptions, config: &Config) -> Result<(), String> {
+    let paths = selection_store::read_selected_paths(config.hold())?;
     if paths.is_empty() {
         config.print_normal("No items selected. Run 'ferry select' first.");
-        return Ok(());
+        return RecordWriter::new(config.format).finish();
     }
 
     config.print_normal(&format!("Copying {} selected items", paths.len()));
@@ -14,44 +45,111 @@ pub fn handle_copy_command(force: bool, config: &Config) -> Result<(), String> {
     let current_dir = std::env::current_dir()
         .map_err(|e| format!("Failed to get current directory: {}", e))?;
 
+    let mut writer = RecordWriter::new(config.format);
+    let mut skipped = Vec::new();
     for source_path in &paths {
-        let file_name = source_path.file_name()
-            .ok_or_else(|| format!("Invalid source path: {}", source_path.display()))?;
-
-        let destination_path = current_dir.join(file_name);
-
-        if destination_path.exists() {
-            if !force {
-                return Err(format!(
-                    "Destination file '{}' already exists. Use --force to overwrite.",
-                    destination_path.display()
-                ));
-            } else {
-                config.print_normal(&format!("Overwriting existing file: {}", destination_path.display()));
+        let record = Record::new(Action::Copy, Status::Ok, source_path);
+        match copy_item(source_path, &current_dir, options.on_conflict, config) {
+            Ok(Transferred::Done(destination_path)) => writer.emit(record.with_destination(&destination_path))?,
+            Ok(Transferred::Skipped(destination_path)) => {
+                skipped.push(source_path.clone());
+                writer.emit(record.with_destination(&destination_path).skipped())?;
+            }
+            Err(e) => {
+                writer.emit(record.failed(&e))?;
+                writer.finish()?;
+                return Err(e);
             }
         }
+    }
+    writer.finish()?;
 
-        fs::copy(source_path, &destination_path)
-            .map_err(|e| format!(
-                "Failed to copy '{}' to '{}': {}",
-                source_path.display(),
-                destination_path.display(),
-                e
-            ))?;
+    if options.keep_selection {
+        config.print_normal("Copy complete. Selection kept.");
+        return Ok(());
+    }
+    finish_transfer("Copy", &skipped, config)
+}
 
-        config.print_normal(&format!("Copied '{}' to '{}'", source_path.display(), destination_path.display()));
+/// What happened to one item of a copy or move.
+enum Transferred {
+    Done(PathBuf),
+    /// The destination exists and the conflict strategy is to skip.
+    Skipped(PathBuf),
+}
+
+/// Clears the selection after a copy or move, keeping the items that were skipped.
+fn finish_transfer(operation: &str, skipped: &[PathBuf], config: &Config) -> Result<(), String> {
+    if skipped.is_empty() {
+        selection_store::clear_selection_file(config.hold())?;
+        config.print_normal(&format!("{} complete. Selection cleared.", operation));
+    } else {
+        selection_store::replace_selected_paths(config.hold(), skipped)?;
+        config.print_normal(&format!(
+            "{} complete. {} skipped items remain selected.",
+            operation, skipped.len()
+        ));
     }
+    Ok(())
+}
+
+/// Decides what to do about an existing destination.
+fn check_conflict(destination_path: &Path, on_conflict: ConflictStrategy, config: &Config) -> Result<bool, String> {
+    if !destination_path.exists() {
+        return Ok(true);
+    }
+    match on_conflict {
+        ConflictStrategy::Fail => Err(format!(
+            "Destination file '{}' already exists. Use --force or --on-conflict to overwrite or skip it.",
+            destination_path.display()
+        )),
+        ConflictStrategy::Skip => {
+            config.print_normal(&format!("Skipping existing file: {}", destination_path.display()));
+            Ok(false)
+        }
+        ConflictStrategy::Overwrite => {
+            config.print_normal(&format!("Overwriting existing file: {}", destination_path.display()));
+            Ok(true)
+        }
+    }
+}
 
-    selection_store::clear_selection_file()?;
-    config.print_normal("Copy complete. Selection cleared.");
+/// Rejects transfers that would put an item into itself or onto itself.
+fn check_destination(mode: TransferMode, source_path: &Path, destination_path: &Path, current_dir: &Path) -> Result<(), String> {
+    if destination_path == source_path {
+        return Err(format!("'{}' is already in this directory.", source_path.display()));
+    }
+    if current_dir.starts_with(source_path) {
+        return Err(format!(
+            "Cannot {} '{}' into itself; run this outside of it.",
+            mode.verb(), source_path.display()
+        ));
+    }
     Ok(())
 }
 
-pub fn handle_move_command(force: bool, config: &Config) -> Result<(), String> {
-    let paths = selection_store::read_selected_paths()?;
+fn copy_item(source_path: &Path, current_dir: &Path, on_conflict: ConflictStrategy, config: &Config) -> Result<Transferred, String> {
+    let file_name = source_path.file_name()
+        .ok_or_else(|| format!("Invalid source path: {}", source_path.display()))?;
+
+    let destination_path = current_dir.join(file_name);
+    check_destination(TransferMode::Copy, source_path, &destination_path, current_dir)?;
+
+    if !check_conflict(&destination_path, on_conflict, config)? {
+        return Ok(Transferred::Skipped(destination_path));
+    }
+
+    transfer(TransferMode::Copy, source_path, &destination_path)?;
+
+    config.print_normal(&format!("Copied '{}' to '{}'", source_path.display(), destination_path.display()));
+    Ok(Transferred::Done(destination_path))
+}
+
+pub fn handle_move_command(options: TransferOptions, config: &Config) -> Result<(), String> {
+    let paths = selection_store::read_selected_paths(config.hold())?;
     if paths.is_empty() {
         config.print_normal("No items selected. Run 'ferry select' first.");
-        return Ok(());
+        return RecordWriter::new(config.format).finish();
     }
 
     config.print_normal(&format!("Moving {} selected items", paths.len()));
@@ -59,48 +157,308 @@ pub fn handle_move_command(force: bool, config: &Config) -> Result<(), String> {
     let current_dir = std::env::current_dir()
         .map_err(|e| format!("Failed to get current directory: {}", e))?;
 
+    let mut writer = RecordWriter::new(config.format);
+    let mut skipped = Vec::new();
     for source_path in &paths {
-        let file_name = source_path.file_name()
-            .ok_or_else(|| format!("Invalid source path: {}", source_path.display()))?;
+        let record = Record::new(Action::Move, Status::Ok, source_path);
+        match move_item(source_path, &current_dir, options.on_conflict, config) {
+            Ok(Transferred::Done(destination_path)) => writer.emit(record.with_destination(&destination_path))?,
+            Ok(Transferred::Skipped(destination_path)) => {
+                skipped.push(source_path.clone());
+                writer.emit(record.with_destination(&destination_path).skipped())?;
+            }
+            Err(e) => {
+                writer.emit(record.failed(&e))?;
+                writer.finish()?;
+                return Err(e);
+            }
+        }
+    }
+    writer.finish()?;
+
+    finish_transfer("Move", &skipped, config)
+}
+
+fn move_item(source_path: &Path, current_dir: &Path, on_conflict: ConflictStrategy, config: &Config) -> Result<Transferred, String> {
+    let file_name = source_path.file_name()
+        .ok_or_else(|| format!("Invalid source path: {}", source_path.display()))?;
+
+    let destination_path = current_dir.join(file_name);
+    check_destination(TransferMode::Move, source_path, &destination_path, current_dir)?;
+
+    if !check_conflict(&destination_path, on_conflict, config)? {
+        return Ok(Transferred::Skipped(destination_path));
+    }
+
+    transfer(TransferMode::Move, source_path, &destination_path)?;
+
+    config.print_normal(&format!("Moved '{}' to '{}'", source_path.display(), destination_path.display()));
+    Ok(Transferred::Done(destination_path))
+}
+
+/// Whether items are copied or moved to their destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferMode {
+    Copy,
+    Move,
+}
+
+impl TransferMode {
+    pub fn action(self) -> Action {
+        match self {
+            TransferMode::Copy => Action::Copy,
+            TransferMode::Move => Action::Move,
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            TransferMode::Copy => TransferMode::Move,
+            TransferMode::Move => TransferMode::Copy,
+        }
+    }
+
+    pub fn verb(self) -> &'static str {
+        match self {
+            TransferMode::Copy => "copy",
+            TransferMode::Move => "move",
+        }
+    }
+}
 
-        let destination_path = current_dir.join(file_name);
+/// What dropping one selected item into a directory will do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// The destination does not exist yet.
+    Create,
+    /// The destination exists and will be replaced.
+    Overwrite,
+    /// Nothing will happen, for the given reason.
+    Skip(String),
+}
 
-        if destination_path.exists() {
-            if !force {
-                return Err(format!(
-                    "Destination file '{}' already exists. Use --force to overwrite.",
-                    destination_path.display()
-                ));
+#[derive(Clone, Debug)]
+pub struct PlannedTransfer {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// Size of the source when planned, as a moved source is gone afterwards.
+    pub size: Option<u64>,
+    pub action: PlannedAction,
+    /// Why the item is skipped unless it overwrites: its destination exists,
+    /// or an earlier item goes to the same place. Such an action can be
+    /// switched between overwriting and skipping.
+    pub conflict: Option<String>,
+}
+
+impl PlannedTransfer {
+    pub fn toggle_overwrite(&mut self) {
+        let Some(reason) = &self.conflict else {
+            return;
+        };
+        self.action = match self.action {
+            PlannedAction::Overwrite => PlannedAction::Skip(reason.clone()),
+            _ => PlannedAction::Overwrite,
+        };
+    }
+}
+
+/// Plans dropping `sources` into `destination_dir`. Existing destinations,
+/// and items with the same name as an earlier one, are overwritten with
+/// `force` and skipped otherwise.
+pub fn plan_transfer(sources: &[PathBuf], destination_dir: &Path, force: bool) -> Vec<PlannedTransfer> {
+    let mut claimed: HashSet<PathBuf> = HashSet::new();
+    sources.iter()
+        .map(|source| {
+            let destination = destination_dir.join(source.file_name().unwrap_or(source.as_os_str()));
+            let metadata = fs::symlink_metadata(source).ok();
+            let mut conflict = None;
+            let action = if metadata.is_none() {
+                PlannedAction::Skip("source no longer exists".to_string())
+            } else if destination == *source {
+                PlannedAction::Skip("already in this directory".to_string())
+            } else if destination_dir.starts_with(source) {
+                PlannedAction::Skip("cannot drop a directory into itself".to_string())
             } else {
-                config.print_normal(&format!("Overwriting existing file: {}", destination_path.display()));
-                if destination_path.is_file() {
-                    fs::remove_file(&destination_path)
-                        .map_err(|e| format!("Failed to remove existing file '{}' before move: {}", destination_path.display(), e))?;
-                } else if destination_path.is_dir() {
-                    fs::remove_dir_all(&destination_path)
-                        .map_err(|e| format!("Failed to remove existing directory '{}' before move: {}", destination_path.display(), e))?;
+                if !claimed.insert(destination.clone()) {
+                    conflict = Some("another item has the same name".to_string());
+                } else if fs::symlink_metadata(&destination).is_ok() {
+                    conflict = Some("already exists".to_string());
                 }
+                match &conflict {
+                    Some(_) if force => PlannedAction::Overwrite,
+                    Some(reason) => PlannedAction::Skip(reason.clone()),
+                    None => PlannedAction::Create,
+                }
+            };
+            PlannedTransfer {
+                source: source.clone(),
+                destination,
+                size: metadata.map(|m| m.len()),
+                action,
+                conflict,
             }
-        }
+        })
+        .collect()
+}
 
-        fs::rename(source_path, &destination_path)
+/// Copies or moves `source` to `destination`, replacing an existing
+/// destination. The new item is built next to it first, so existing
+/// directories are replaced rather than merged into, and nothing is removed
+/// before the new data is in place. Moves that cannot rename because they
+/// cross file systems copy and then remove the source.
+pub fn transfer(mode: TransferMode, source: &Path, destination: &Path) -> Result<(), String> {
+    let exists = fs::symlink_metadata(destination).is_ok();
+    let target = if exists { fs_helpers::staging_path(destination) } else { destination.to_path_buf() };
+    let mut copied = mode == TransferMode::Copy;
+    let result = match mode {
+        TransferMode::Copy => fs_helpers::copy_recursively(source, &target),
+        TransferMode::Move => match fs::rename(source, &target) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                copied = true;
+                fs_helpers::copy_recursively(source, &target)
+            }
+            result => result,
+        },
+    };
+    if let Err(e) = result {
+        // A failed rename leaves nothing behind; a partial copy is cleaned up.
+        if copied {
+            let _ = fs_helpers::remove_path(&target);
+        }
+        return Err(format!(
+            "Failed to {} '{}' to '{}': {}",
+            mode.verb(),
+            source.display(),
+            destination.display(),
+            e
+        ));
+    }
+    if exists
+        && let Err(e) = replace_with_staged(&target, destination)
+    {
+        // A renamed move leaves the only copy of the data in staging, so
+        // put it back where it came from; otherwise say where it is.
+        if mode == TransferMode::Move && !copied && fs::rename(&target, source).is_ok() {
+            return Err(e);
+        }
+        return Err(format!("{} (the staged item is left at '{}')", e, target.display()));
+    }
+    if mode == TransferMode::Move && copied {
+        fs_helpers::remove_path(source)
             .map_err(|e| format!(
-                "Failed to move '{}' to '{}': {}",
-                source_path.display(),
-                destination_path.display(),
+                "Copied '{}' to '{}' but failed to remove the original: {}",
+                source.display(),
+                destination.display(),
                 e
             ))?;
+    }
+    Ok(())
+}
+
+/// Swaps the staged copy at `staged` in for the existing `destination`.
+fn replace_with_staged(staged: &Path, destination: &Path) -> Result<(), String> {
+    fs_helpers::remove_path(destination)
+        .map_err(|e| format!("Failed to remove existing '{}': {}", destination.display(), e))?;
+    fs::rename(staged, destination)
+        .map_err(|e| format!(
+            "Failed to rename '{}' to '{}': {}",
+            staged.display(),
+            destination.display(),
+            e
+        ))
+}
 
-        config.print_normal(&format!("Moved '{}' to '{}'", source_path.display(), destination_path.display()));
+pub fn handle_drop_command(
+    interactive: bool,
+    path: Option<String>,
+    mode: TransferMode,
+    options: TransferOptions,
+    config: &Config,
+) -> Result<(), String> {
+    if !interactive {
+        return match mode {
+            TransferMode::Copy => handle_copy_command(options, config),
+            TransferMode::Move => handle_move_command(options, config),
+        };
     }
 
-    selection_store::clear_selection_file()?;
-    config.print_normal("Move complete. Selection cleared.");
+    let paths = selection_store::read_selected_paths(config.hold())?;
+    if paths.is_empty() {
+        config.print_normal("No items selected. Run 'ferry select' first.");
+        return RecordWriter::new(config.format).finish();
+    }
+    drop_paths(&paths, path, mode, options, config)
+}
+
+/// Runs the destination browser on `paths`, then stores what was not dropped
+/// (all of `paths` with keep_selection and a copy) as the selection. The
+/// stored selection is left alone if the drop is cancelled.
+fn drop_paths(
+    paths: &[PathBuf],
+    path: Option<String>,
+    mode: TransferMode,
+    options: TransferOptions,
+    config: &Config,
+) -> Result<(), String> {
+    let start_path = match path {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?,
+    };
+
+    let (mode, results) = match interactive::run_drop_tui(paths, &start_path, mode, options.on_conflict == ConflictStrategy::Overwrite, config)? {
+        DropOutcome::Cancelled => {
+            config.print_normal("Drop cancelled. Nothing was copied or moved.");
+            return RecordWriter::new(config.format).finish();
+        }
+        DropOutcome::Finished { mode, results } => (mode, results),
+    };
+
+    let mut writer = RecordWriter::new(config.format);
+    let mut remaining = Vec::new();
+    let (mut done, mut skipped, mut failed) = (0, 0, 0);
+    for (planned, result) in &results {
+        let record = Record::new(mode.action(), Status::Ok, &planned.source)
+            .with_destination(&planned.destination)
+            .with_size(planned.size);
+        match (&planned.action, result) {
+            (PlannedAction::Skip(reason), _) => {
+                config.print_verbose(&format!("Skipped '{}': {}", planned.source.display(), reason));
+                skipped += 1;
+                remaining.push(planned.source.clone());
+                writer.emit(record.skipped())?;
+            }
+            (_, Some(e)) => {
+                config.print_warning(e);
+                failed += 1;
+                remaining.push(planned.source.clone());
+                writer.emit(record.failed(e))?;
+            }
+            (_, None) => {
+                done += 1;
+                writer.emit(record)?;
+            }
+        }
+    }
+    writer.finish()?;
+
+    // Whatever was not dropped stays selected for another try, and with
+    // keep_selection so does everything that was only copied.
+    let kept = if options.keep_selection && mode == TransferMode::Copy { paths } else { &remaining[..] };
+    selection_store::replace_selected_paths(config.hold(), kept)?;
+    config.print_normal(&format!(
+        "Dropped {} items ({}), {} skipped, {} failed.",
+        done, mode.verb(), skipped, failed
+    ));
+    if failed > 0 {
+        return Err(format!("{} items could not be dropped.", failed));
+    }
     Ok(())
 }
 
 pub fn handle_list_command(_absolute: bool, relative: bool, config: &Config) -> Result<(), String> {
-    let paths = selection_store::read_selected_paths()?;
+    let paths = selection_store::read_selected_paths(config.hold())?;
+    let mut writer = RecordWriter::new(config.format);
 
     if paths.is_empty() {
         config.print_normal("No files currently selected.");
@@ -112,14 +470,57 @@ pub fn handle_list_command(_absolute: bool, relative: bool, config: &Config) ->
         for path in paths {
             let display_path = if relative {
                 path.strip_prefix(&current_dir)
-                    .unwrap_or(&path) 
-                    .display()
-                    .to_string()
+                    .unwrap_or(&path)
+                    .to_path_buf()
             } else {
-                path.display().to_string()
+                path.clone()
             };
-            config.print_normal(&format!("  {}", display_path));
+            if config.is_machine_readable() {
+                let size = fs::symlink_metadata(&path).ok().map(|m| m.len());
+                writer.emit(Record::new(Action::List, Status::Ok, &display_path).with_size(size))?;
+            } else {
+                config.print_normal(&format!("  {}", display_path.display()));
+            }
         }
     }
-    Ok(())
-}
\ No newline at end of file
+    writer.finish()
+}
+
+/// Opens the stored selection in the manager TUI and saves the edited
+/// selection, or hands it to the destination browser if a copy or move was
+/// asked for.
+pub fn handle_manage_command(config: &Config) -> Result<(), String> {
+    let paths = selection_store::read_selected_paths(config.hold())?;
+    if paths.is_empty() {
+        config.print_normal("No files currently selected.");
+        return RecordWriter::new(config.format).finish();
+    }
+
+    match interactive::run_manager_tui(&paths, config)? {
+        ManagerOutcome::Discarded => {
+            config.print_normal("Selection unchanged.");
+            RecordWriter::new(config.format).finish()
+        }
+        ManagerOutcome::Saved(paths) => {
+            selection_store::replace_selected_paths(config.hold(), &paths)?;
+            if paths.is_empty() {
+                config.print_normal("Cleared the selection.");
+            } else {
+                config.print_normal(&format!("Saved {} items to the selection.", paths.len()));
+            }
+            let mut writer = RecordWriter::new(config.format);
+            for path in &paths {
+                writer.emit(Record::new(Action::List, Status::Ok, path))?;
+            }
+            writer.finish()
+        }
+        // The edits are only stored once the drop has run.
+        ManagerOutcome::Drop(paths, _) if paths.is_empty() => {
+            config.print_normal("Nothing left to drop. Selection unchanged.");
+            RecordWriter::new(config.format).finish()
+        }
+        ManagerOutcome::Drop(paths, mode) => {
+            drop_paths(&paths, None, mode, TransferOptions::from_settings(&config.settings.transfer), config)
+        }
+    }
+}
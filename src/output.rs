@@ -0,0 +1,148 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::cli::OutputFormat;
+use crate::utils::fs_helpers;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    List,
+    Select,
+    Copy,
+    Move,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The action was carried out.
+    Ok,
+    /// The action would be carried out, but this is a dry run.
+    Planned,
+    /// The action was attempted and failed; see `error`.
+    Failed,
+    /// The action was not attempted, e.g. because the destination exists.
+    Skipped,
+}
+
+/// One structured output record, describing what happened to a single path.
+#[derive(Debug, Serialize)]
+pub struct Record {
+    #[serde(serialize_with = "serialize_lossy")]
+    pub source: PathBuf,
+    #[serde(serialize_with = "serialize_lossy_option")]
+    pub destination: Option<PathBuf>,
+    pub size: Option<u64>,
+    pub action: Action,
+    pub status: Status,
+    pub error: Option<String>,
+}
+
+impl Record {
+    pub fn new(action: Action, status: Status, source: &Path) -> Self {
+        Record {
+            source: source.to_path_buf(),
+            destination: None,
+            size: std::fs::symlink_metadata(source).ok().map(|m| m.len()),
+            action,
+            status,
+            error: None,
+        }
+    }
+
+    pub fn with_destination(mut self, destination: &Path) -> Self {
+        self.destination = Some(destination.to_path_buf());
+        self
+    }
+
+    /// The size is read from `source` when the record is created; callers that
+    /// display a path differently from where it lives pass the size explicitly.
+    pub fn with_size(mut self, size: Option<u64>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn skipped(mut self) -> Self {
+        self.status = Status::Skipped;
+        self
+    }
+
+    pub fn failed(mut self, error: &str) -> Self {
+        self.status = Status::Failed;
+        self.error = Some(error.to_string());
+        self
+    }
+}
+
+/// Writes records to stdout in the format chosen with `--format`.
+///
+/// `json` buffers everything and prints a single array from `finish`, `jsonl`
+/// prints one object per line as records arrive, and `null` prints the path of
+/// every successful record (the destination if there is one) followed by a NUL
+/// byte, for `xargs -0`. In `text` mode records are dropped, since the human
+/// readable messages are printed through `Config` instead.
+pub struct RecordWriter {
+    format: OutputFormat,
+    buffered: Vec<Record>,
+}
+
+impl RecordWriter {
+    pub fn new(format: OutputFormat) -> Self {
+        RecordWriter { format, buffered: Vec::new() }
+    }
+
+    pub fn emit(&mut self, record: Record) -> Result<(), String> {
+        match self.format {
+            OutputFormat::Text => Ok(()),
+            OutputFormat::Json => {
+                self.buffered.push(record);
+                Ok(())
+            }
+            OutputFormat::Jsonl => {
+                let line = serde_json::to_string(&record)
+                    .map_err(|e| format!("Failed to serialize output record: {}", e))?;
+                write_stdout(format!("{}\n", line).as_bytes())
+            }
+            OutputFormat::Null => {
+                if matches!(record.status, Status::Failed | Status::Skipped) {
+                    return Ok(());
+                }
+                let path = record.destination.as_deref().unwrap_or(&record.source);
+                let mut bytes = fs_helpers::path_to_bytes(path)
+                    .unwrap_or_else(|| path.to_string_lossy().into_owned().into_bytes());
+                bytes.push(b'\0');
+                write_stdout(&bytes)
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        if self.format == OutputFormat::Json {
+            let array = serde_json::to_string_pretty(&self.buffered)
+                .map_err(|e| format!("Failed to serialize output records: {}", e))?;
+            write_stdout(format!("{}\n", array).as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+// Paths that are not valid UTF-8 cannot be represented in JSON, so they are
+// written with replacement characters; `--format null` keeps the raw bytes.
+fn serialize_lossy<S: serde::Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+fn serialize_lossy_option<S: serde::Serializer>(path: &Option<PathBuf>, serializer: S) -> Result<S::Ok, S::Error> {
+    match path {
+        Some(path) => serialize_lossy(path, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn write_stdout(bytes: &[u8]) -> Result<(), String> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(bytes)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("Failed to write to standard output: {}", e))
+}
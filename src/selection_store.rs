@@ -1,61 +1,115 @@
 use std::path::PathBuf;
 use std::fs;
 use directories::ProjectDirs;
+use crate::utils::fs_helpers;
 
-const APP_QUALIFIER: &str = "com";
-const APP_ORGANIZATION: &str = "ferry-cli";
-const APP_NAME: &str = "ferry";
+pub(crate) const APP_QUALIFIER: &str = "com";
+pub(crate) const APP_ORGANIZATION: &str = "ferry-cli";
+pub(crate) const APP_NAME: &str = "ferry";
 const SELECTION_FILE_NAME: &str = "selection";
+const HOLDS_DIR_NAME: &str = "holds";
+pub const DEFAULT_HOLD_NAME: &str = "default";
+
+/// Overrides the directory the selections are stored in.
+pub const CACHE_DIR_VAR: &str = "FERRY_CACHE_DIR";
+
+/// Where the selections live: `FERRY_CACHE_DIR` if set, otherwise the
+/// platform's cache directory.
+pub fn cache_dir_path() -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var_os(CACHE_DIR_VAR).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    ProjectDirs::from(APP_QUALIFIER, APP_ORGANIZATION, APP_NAME)
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+        .ok_or_else(|| "Could not determine a suitable cache directory for Ferry.".to_string())
+}
 
 fn get_ferry_data_dir() -> Result<PathBuf, String> {
-    if let Some(proj_dirs) = ProjectDirs::from(APP_QUALIFIER, APP_ORGANIZATION, APP_NAME) {
-        let cache_dir = proj_dirs.cache_dir().to_path_buf();
-        fs::create_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to create cache directory {}: {}", cache_dir.display(), e))?;
-        Ok(cache_dir)
-    } else {
-        Err("Could not determine a suitable cache directory for Ferry.".to_string())
+    let cache_dir = cache_dir_path()?;
+    fs::create_dir_all(&cache_dir)
+        .map_err(|e| format!("Failed to create cache directory {}: {}", cache_dir.display(), e))?;
+    Ok(cache_dir)
+}
+
+fn validate_hold_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return Err(format!(
+            "Invalid selection name '{}'. Use letters, digits, '-', '_' or '.' (not as the first character).",
+            name
+        ));
     }
+    Ok(())
 }
 
-pub fn get_selection_file_path() -> Result<PathBuf, String> {
+/// Returns the file backing a selection. `None` (or the name "default") is the
+/// unnamed selection used when no `--hold` is given.
+pub fn get_selection_file_path(hold: Option<&str>) -> Result<PathBuf, String> {
     let mut path = get_ferry_data_dir()?;
-    path.push(SELECTION_FILE_NAME);
+    match hold {
+        None | Some(DEFAULT_HOLD_NAME) => path.push(SELECTION_FILE_NAME),
+        Some(name) => {
+            validate_hold_name(name)?;
+            path.push(HOLDS_DIR_NAME);
+            fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create selection directory {}: {}", path.display(), e))?;
+            path.push(name);
+        }
+    }
     Ok(path)
 }
 
-pub fn write_selected_paths(new_paths: &[PathBuf]) -> Result<(), String> {
-    let file_path = get_selection_file_path()?;
-    let mut existing_paths = read_selected_paths()?;
+pub fn write_selected_paths(hold: Option<&str>, new_paths: &[PathBuf]) -> Result<(), String> {
+    let mut existing_paths = read_selected_paths(hold)?;
     for new_path in new_paths {
         if !existing_paths.contains(new_path) {
             existing_paths.push(new_path.clone());
         }
     }
+    replace_selected_paths(hold, &existing_paths)
+}
 
-    let content: Vec<String> = existing_paths.iter()
-                                               .filter_map(|p| p.to_str().map(|s| s.to_string()))
-                                               .collect();
+/// Overwrites a selection with exactly `paths`, dropping whatever it held before.
+pub fn replace_selected_paths(hold: Option<&str>, paths: &[PathBuf]) -> Result<(), String> {
+    let file_path = get_selection_file_path(hold)?;
+    let content: Vec<Vec<u8>> = paths.iter()
+                                     .filter_map(|p| fs_helpers::path_to_bytes(p))
+                                     .collect();
 
-    fs::write(&file_path, content.join("\n"))
+    fs::write(&file_path, content.join(&b'\n'))
         .map_err(|e| format!("Failed to write selection to {}: {}", file_path.display(), e))
 }
 
-pub fn read_selected_paths() -> Result<Vec<PathBuf>, String> {
-    let file_path = get_selection_file_path()?;
+pub fn read_selected_paths(hold: Option<&str>) -> Result<Vec<PathBuf>, String> {
+    let file_path = get_selection_file_path(hold)?;
     if !file_path.exists() {
         return Ok(Vec::new());
     }
-    let content = fs::read_to_string(&file_path)
+    let content = fs::read(&file_path)
         .map_err(|e| format!("Failed to read selection from {}: {}", file_path.display(), e))?;
-    Ok(content.lines().map(PathBuf::from).collect())
+    Ok(content.split(|b| *b == b'\n')
+              .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+              .filter(|line| !line.is_empty())
+              .map(fs_helpers::path_from_bytes)
+              .collect())
 }
 
-pub fn clear_selection_file() -> Result<(), String> {
-    let file_path = get_selection_file_path()?;
+/// Like [`read_selected_paths`], but a hold without a selection file is an
+/// error rather than empty, to catch mistyped names.
+pub fn read_existing_selection(hold: &str) -> Result<Vec<PathBuf>, String> {
+    if !get_selection_file_path(Some(hold))?.exists() {
+        return Err(format!("Selection '{}' does not exist.", hold));
+    }
+    read_selected_paths(Some(hold))
+}
+
+pub fn clear_selection_file(hold: Option<&str>) -> Result<(), String> {
+    let file_path = get_selection_file_path(hold)?;
     if file_path.exists() {
         fs::remove_file(&file_path)
             .map_err(|e| format!("Failed to clear selection file {}: {}", file_path.display(), e))?;
     }
     Ok(())
-}
\ No newline at end of file
+}
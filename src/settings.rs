@@ -0,0 +1,556 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use directories::ProjectDirs;
+use clap::{ArgMatches, CommandFactory, ValueEnum};
+use clap::parser::ValueSource;
+use serde::{Deserialize, Serialize};
+use crate::cli::{Cli, ConfigAction, ConflictStrategy, SelectionMode, TraversalArgs};
+use crate::config::Config;
+use crate::selection_store::{self, APP_NAME, APP_ORGANIZATION, APP_QUALIFIER};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+/// Per-project settings, looked up in the current directory and its ancestors.
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".ferry.toml";
+/// Points at a user config file to use instead of the default one.
+pub const CONFIG_PATH_VAR: &str = "FERRY_CONFIG";
+/// Environment variables that set a single config key, and how their values
+/// are read. They override both config files.
+const ENV_KEYS: [(&str, &str, EnvKind); 7] = [
+    ("FERRY_ON_CONFLICT", "transfer.on_conflict", EnvKind::Text),
+    ("FERRY_KEEP_SELECTION", "transfer.keep_selection", EnvKind::Flag),
+    ("FERRY_SELECT_MODE", "select.mode", EnvKind::Text),
+    ("FERRY_EXCLUDE", "select.exclude", EnvKind::List),
+    ("FERRY_NO_IGNORE", "select.no_ignore", EnvKind::Flag),
+    ("FERRY_HIDDEN", "select.hidden", EnvKind::Flag),
+    ("FERRY_THEME", "tui.theme", EnvKind::Text),
+];
+/// `FERRY_KEYMAP_DOWN=j,down` sets `tui.keymap.down`.
+const KEYMAP_ENV_PREFIX: &str = "FERRY_KEYMAP_";
+/// `FERRY_COLOR_CURSOR="black on yellow"` sets `tui.colors.cursor`.
+const COLOR_ENV_PREFIX: &str = "FERRY_COLOR_";
+/// The global options, with the environment variables clap reads them from.
+const GLOBAL_OPTIONS: [(&str, &str); 4] = [
+    ("silent", "FERRY_SILENT"),
+    ("verbose", "FERRY_VERBOSE"),
+    ("hold", "FERRY_HOLD"),
+    ("format", "FERRY_FORMAT"),
+];
+/// Written by `ferry config edit` when the file does not exist yet.
+const CONFIG_TEMPLATE: &str = r#"# Ferry configuration. Every key is optional; see `ferry config show` for the defaults.
+
+# [transfer]
+# on_conflict = "fail"      # fail, skip or overwrite
+# keep_selection = false    # keep the selection after copying
+
+# [select]
+# mode = "add"              # add to the stored selection, or "replace" it
+# exclude = ["target", "node_modules"]
+# no_ignore = false
+# hidden = true
+
+# [tui]
+# theme = "default"         # default, light or mono
+
+# [tui.keymap]
+# down = ["j", "down"]
+
+# [tui.colors]
+# cursor = "black on cyan"
+"#;
+
+/// Settings read from the ferry config files. Every section is optional.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub transfer: TransferSettings,
+    pub select: SelectSettings,
+    pub tui: TuiSettings,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransferSettings {
+    /// What copy, move and drop do when the destination already exists.
+    pub on_conflict: ConflictStrategy,
+    /// Keep the selection after a successful copy instead of clearing it.
+    pub keep_selection: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectSettings {
+    /// Whether direct, regex, glob and stdin selections add to the stored
+    /// selection or replace it.
+    pub mode: SelectionMode,
+    /// Glob patterns skipped by every search unless `--exclude` is given.
+    pub exclude: Vec<String>,
+    /// Do not honour .gitignore, .ignore and .ferryignore files unless
+    /// `--ignore` is given.
+    pub no_ignore: bool,
+    /// Visit hidden files unless `--no-hidden` is given.
+    pub hidden: bool,
+}
+
+impl Default for SelectSettings {
+    fn default() -> Self {
+        SelectSettings { mode: SelectionMode::Add, exclude: Vec::new(), no_ignore: false, hidden: true }
+    }
+}
+
+impl SelectSettings {
+    /// Fills in the configured ignore rules where the command line leaves
+    /// them open; explicit flags win.
+    pub fn apply_to(&self, traversal: &mut TraversalArgs) {
+        if traversal.exclude.is_empty() {
+            traversal.exclude = self.exclude.clone();
+        }
+        if self.no_ignore && !traversal.ignore {
+            traversal.no_ignore = true;
+        }
+        if !self.hidden && !traversal.hidden {
+            traversal.no_hidden = true;
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TuiSettings {
+    /// Action name to the keys bound to it, e.g. `down = ["j", "down"]`.
+    /// Listed actions replace their default keys; others keep them.
+    pub keymap: BTreeMap<String, Vec<String>>,
+    /// One of the built-in themes; `default` when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// Style overrides on top of the theme, e.g. `cursor = "black on yellow"`.
+    pub colors: BTreeMap<String, String>,
+}
+
+/// A config file that exists and was read.
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub origin: Origin,
+    pub table: toml::Table,
+}
+
+/// Where the value of a setting comes from, as shown by
+/// `ferry config show --origin`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    UserFile(PathBuf),
+    ProjectFile(PathBuf),
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::UserFile(path) => write!(f, "user config {}", path.display()),
+            Origin::ProjectFile(path) => write!(f, "project config {}", path.display()),
+            Origin::Env(var) => write!(f, "env {}", var),
+            Origin::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum EnvKind {
+    Text,
+    /// `1`, `true`, `yes` or `on`, and `0`, `false`, `no` or `off`.
+    Flag,
+    /// Comma-separated.
+    List,
+}
+
+/// The user config file: `FERRY_CONFIG` if set, otherwise `config.toml` in
+/// the platform's config directory.
+pub fn get_config_file_path() -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_VAR).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    ProjectDirs::from(APP_QUALIFIER, APP_ORGANIZATION, APP_NAME)
+        .map(|dirs| dirs.config_dir().join(CONFIG_FILE_NAME))
+        .ok_or_else(|| "Could not determine a suitable config directory for Ferry.".to_string())
+}
+
+/// The nearest `.ferry.toml` in the current directory or one of its ancestors.
+pub fn find_project_config_file() -> Option<PathBuf> {
+    let current_dir = std::env::current_dir().ok()?;
+    current_dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The config files that exist, lowest precedence first: the user config,
+/// then the project config.
+pub fn config_files() -> Result<Vec<ConfigFile>, String> {
+    let user_file = get_config_file_path()?;
+    let candidates = [
+        Some((user_file.clone(), Origin::UserFile(user_file))),
+        find_project_config_file().map(|path| (path.clone(), Origin::ProjectFile(path))),
+    ];
+    let mut files = Vec::new();
+    for (path, origin) in candidates.into_iter().flatten() {
+        if let Some(table) = read_config_file(&path)? {
+            files.push(ConfigFile { path, origin, table });
+        }
+    }
+    Ok(files)
+}
+
+/// The `FERRY_*` variables that set config keys, each as a table holding
+/// just that key. Sorted by variable name so the result does not depend on
+/// the order of the environment.
+fn env_overrides() -> Result<Vec<(Origin, toml::Table)>, String> {
+    let mut vars: Vec<(String, String, EnvKind)> = Vec::new();
+    for (var, key, kind) in ENV_KEYS {
+        if std::env::var_os(var).is_some() {
+            vars.push((var.to_string(), key.to_string(), kind));
+        }
+    }
+    for (var, _) in std::env::vars_os() {
+        let Some(var) = var.to_str() else {
+            continue;
+        };
+        if let Some(action) = var.strip_prefix(KEYMAP_ENV_PREFIX).filter(|a| !a.is_empty()) {
+            vars.push((var.to_string(), format!("tui.keymap.{}", action.to_lowercase()), EnvKind::List));
+        } else if let Some(name) = var.strip_prefix(COLOR_ENV_PREFIX).filter(|n| !n.is_empty()) {
+            vars.push((var.to_string(), format!("tui.colors.{}", name.to_lowercase()), EnvKind::Text));
+        }
+    }
+    vars.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut overrides = Vec::new();
+    for (var, key, kind) in vars {
+        let raw = std::env::var(&var)
+            .map_err(|_| format!("Invalid value for {}: not valid UTF-8", var))?;
+        let value = match kind {
+            EnvKind::Text => toml::Value::String(raw),
+            EnvKind::Flag => toml::Value::Boolean(parse_flag(&raw)
+                .ok_or_else(|| format!("Invalid value for {}: expected true or false, got '{}'", var, raw))?),
+            EnvKind::List => toml::Value::Array(raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect()),
+        };
+        let table = nested_table(&key, value);
+        // Checked on its own so errors name the variable they come from.
+        Settings::deserialize(table.clone())
+            .map_err(|e| format!("Invalid value for {}: {}", var, e.to_string().trim_end()))?;
+        overrides.push((Origin::Env(var), table));
+    }
+    Ok(overrides)
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "" | "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// `{ a = { b = value } }` for the key `a.b`.
+fn nested_table(key: &str, value: toml::Value) -> toml::Table {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or_default();
+    let mut table = toml::Table::from_iter([(last.to_string(), value)]);
+    for part in parts.into_iter().rev() {
+        table = toml::Table::from_iter([(part.to_string(), toml::Value::Table(table))]);
+    }
+    table
+}
+
+/// Reads and validates one config file; `None` if it does not exist.
+fn read_config_file(path: &Path) -> Result<Option<toml::Table>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read config file {}: {}", path.display(), e)),
+    };
+    let table: toml::Table = toml::from_str(&contents)
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+    // Checked on its own so errors name the file they come from.
+    Settings::deserialize(table.clone())
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+    Ok(Some(table))
+}
+
+/// Reads the config files and the `FERRY_*` variables, later ones overriding
+/// single keys of earlier ones, and falls back to defaults for everything
+/// they leave out.
+pub fn load() -> Result<Settings, String> {
+    load_with_origins().map(|(settings, _)| settings)
+}
+
+/// Like [`load`], also returning the layer each key was last set by, keyed
+/// by its dotted name. Keys missing from the map have their default value.
+pub fn load_with_origins() -> Result<(Settings, BTreeMap<String, Origin>), String> {
+    let mut layers: Vec<(Origin, toml::Table)> = config_files()?
+        .into_iter()
+        .map(|file| (file.origin, file.table))
+        .collect();
+    layers.extend(env_overrides()?);
+
+    let (merged, origins) = merge_layers(layers);
+    let settings = Settings::deserialize(merged).map_err(|e| format!("Invalid configuration: {}", e))?;
+    Ok((settings, origins))
+}
+
+/// Merges `layers` in order and records which layer set each key last.
+fn merge_layers(layers: Vec<(Origin, toml::Table)>) -> (toml::Table, BTreeMap<String, Origin>) {
+    let mut merged = toml::Table::new();
+    let mut origins = BTreeMap::new();
+    for (origin, table) in layers {
+        for (key, _) in flatten_table(&table) {
+            origins.insert(key, origin.clone());
+        }
+        merge_tables(&mut merged, table);
+    }
+    (merged, origins)
+}
+
+/// Every value that is not a table, with its dotted key.
+fn flatten_table(table: &toml::Table) -> Vec<(String, &toml::Value)> {
+    let mut entries = Vec::new();
+    for (key, value) in table {
+        match value {
+            toml::Value::Table(inner) => entries.extend(flatten_table(inner)
+                .into_iter()
+                .map(|(inner_key, value)| (format!("{}.{}", key, inner_key), value))),
+            value => entries.push((key.clone(), value)),
+        }
+    }
+    entries
+}
+
+/// Merges `overrides` into `base`, recursing into tables so that a project
+/// file setting one key of a section keeps the user's other keys.
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => merge_tables(base_table, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+pub fn handle_config_command(action: ConfigAction, matches: &ArgMatches, config: &Config) -> Result<(), String> {
+    match action {
+        ConfigAction::Show { origin: false } => {
+            let files = config_files()?;
+            if files.is_empty() {
+                config.print_normal("# No config file found; showing the defaults.");
+            }
+            for file in &files {
+                config.print_normal(&format!("# Read from {}", file.path.display()));
+            }
+            let settings = load()?;
+            let contents = toml::to_string(&settings)
+                .map_err(|e| format!("Failed to serialize the settings: {}", e))?;
+            config.print_normal(contents.trim_end());
+            Ok(())
+        }
+        ConfigAction::Show { origin: true } => {
+            let (settings, origins) = load_with_origins()?;
+            let table = toml::Table::try_from(&settings)
+                .map_err(|e| format!("Failed to serialize the settings: {}", e))?;
+            for (key, value) in flatten_table(&table) {
+                let origin = origins.get(&key).unwrap_or(&Origin::Default);
+                config.print_normal(&format!("{} = {}  # {}", key, value, origin));
+            }
+            for (id, var) in GLOBAL_OPTIONS {
+                let value = match id {
+                    "silent" => toml::Value::Boolean(config.silent),
+                    "verbose" => toml::Value::Boolean(config.verbose),
+                    "hold" => toml::Value::String(config.hold().unwrap_or(selection_store::DEFAULT_HOLD_NAME).to_string()),
+                    _ => toml::Value::String(config.format.to_possible_value()
+                        .map(|value| value.get_name().to_string())
+                        .unwrap_or_default()),
+                };
+                let origin = match matches.value_source(id) {
+                    Some(ValueSource::CommandLine) => Origin::CommandLine,
+                    Some(ValueSource::EnvVariable) => Origin::Env(var.to_string()),
+                    _ => Origin::Default,
+                };
+                config.print_normal(&format!("--{} = {}  # {}", id, value, origin));
+            }
+            // The command options cannot be given to `config show`, so they
+            // come from their variable or their default.
+            let mut command = Cli::command();
+            command.build();
+            for subcommand in command.get_subcommands() {
+                for arg in subcommand.get_arguments().filter(|arg| !arg.is_global_set()) {
+                    let Some(var) = arg.get_env().and_then(|var| var.to_str()) else {
+                        continue;
+                    };
+                    let name = arg.get_long().unwrap_or(arg.get_id().as_str());
+                    let (value, origin) = match std::env::var(var) {
+                        Ok(value) => (toml::Value::String(value).to_string(), Origin::Env(var.to_string())),
+                        Err(_) => (default_value_label(arg), Origin::Default),
+                    };
+                    config.print_normal(&format!("{} --{} = {}  # {}", subcommand.get_name(), name, value, origin));
+                }
+            }
+            let cache_origin = match std::env::var_os(selection_store::CACHE_DIR_VAR).filter(|p| !p.is_empty()) {
+                Some(_) => Origin::Env(selection_store::CACHE_DIR_VAR.to_string()),
+                None => Origin::Default,
+            };
+            let cache_dir = selection_store::cache_dir_path()?;
+            config.print_normal(&format!(
+                "cache_dir = {}  # {}",
+                toml::Value::String(cache_dir.display().to_string()), cache_origin
+            ));
+            Ok(())
+        }
+        ConfigAction::Path { project } => {
+            let path = if project { project_config_path()? } else { get_config_file_path()? };
+            config.print_normal(&path.display().to_string());
+            Ok(())
+        }
+        ConfigAction::Edit { project } => {
+            let path = if project { project_config_path()? } else { get_config_file_path()? };
+            if !path.exists() {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create config directory {}: {}", parent.display(), e))?;
+                }
+                fs::write(&path, CONFIG_TEMPLATE)
+                    .map_err(|e| format!("Failed to create config file {}: {}", path.display(), e))?;
+                config.print_verbose(&format!("Created {}", path.display()));
+            }
+            open_in_editor(&path)?;
+            // Report mistakes now rather than on the next command.
+            match read_config_file(&path) {
+                Ok(_) => config.print_normal(&format!("Saved {}", path.display())),
+                Err(e) => config.print_warning(&e),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// An option's default as shown by `config show --origin`.
+fn default_value_label(arg: &clap::Arg) -> String {
+    let defaults: Vec<String> = arg.get_default_values().iter()
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect();
+    match defaults.as_slice() {
+        [] => "(none)".to_string(),
+        [value] => toml::Value::String(value.clone()).to_string(),
+        values => toml::Value::Array(values.iter().cloned().map(toml::Value::String).collect()).to_string(),
+    }
+}
+
+/// The nearest project config file, or a new one in the current directory.
+fn project_config_path() -> Result<PathBuf, String> {
+    match find_project_config_file() {
+        Some(path) => Ok(path),
+        None => std::env::current_dir()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+            .map_err(|e| format!("Failed to get current directory: {}", e)),
+    }
+}
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Runs `$VISUAL`, `$EDITOR` or a platform default on `path` and waits for it.
+/// The variables may carry arguments, e.g. `code --wait`.
+fn open_in_editor(path: &Path) -> Result<(), String> {
+    let editor = ["VISUAL", "EDITOR"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Failed to run editor '{}': {}", program, e))?;
+    if !status.success() {
+        return Err(format!("Editor '{}' exited with {}", program, status));
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(text: &str) -> toml::Table {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn parse_flag_accepts_common_spellings() {
+        for value in ["1", "true", "YES", " on "] {
+            assert_eq!(parse_flag(value), Some(true), "{:?}", value);
+        }
+        for value in ["", "0", "False", "no", "off"] {
+            assert_eq!(parse_flag(value), Some(false), "{:?}", value);
+        }
+        assert_eq!(parse_flag("maybe"), None);
+        assert_eq!(parse_flag("2"), None);
+    }
+
+    #[test]
+    fn nested_table_splits_dotted_keys() {
+        assert_eq!(nested_table("tui.keymap.quit", toml::Value::from("q")), table("tui.keymap.quit = 'q'"));
+        assert_eq!(nested_table("hidden", toml::Value::from(true)), table("hidden = true"));
+    }
+
+    #[test]
+    fn flatten_table_lists_leaf_values_with_dotted_keys() {
+        let flattened = table("a = 1\n[b]\nc = 'x'\nd.e = ['y']\n");
+        let mut keys: Vec<String> = flatten_table(&flattened).into_iter().map(|(key, _)| key).collect();
+        keys.sort();
+        assert_eq!(keys, ["a", "b.c", "b.d.e"]);
+        assert!(flatten_table(&toml::Table::new()).is_empty());
+    }
+
+    #[test]
+    fn merge_tables_overrides_single_keys_of_a_section() {
+        let mut base = table("[select]\nhidden = true\nexclude = ['target']\n[tui]\npreview = true\n");
+        merge_tables(&mut base, table("[select]\nhidden = false\n"));
+        assert_eq!(base, table("[select]\nhidden = false\nexclude = ['target']\n[tui]\npreview = true\n"));
+    }
+
+    #[test]
+    fn merge_tables_replaces_arrays_and_non_table_values_whole() {
+        let mut base = table("[select]\nexclude = ['a', 'b']\n");
+        merge_tables(&mut base, table("[select]\nexclude = ['c']\n"));
+        assert_eq!(base, table("[select]\nexclude = ['c']\n"));
+        merge_tables(&mut base, table("select = 'flat'"));
+        assert_eq!(base, table("select = 'flat'"));
+    }
+
+    #[test]
+    fn project_keys_override_user_keys_and_env_overrides_both() {
+        let user = Origin::UserFile(PathBuf::from("user.toml"));
+        let project = Origin::ProjectFile(PathBuf::from(".ferry.toml"));
+        let env = Origin::Env("FERRY_HIDDEN".to_string());
+        let (merged, origins) = merge_layers(vec![
+            (user.clone(), table("[select]\nhidden = true\nmode = 'add'\nexclude = ['target']\n")),
+            (project.clone(), table("[select]\nhidden = false\nmode = 'replace'\n")),
+            (env.clone(), nested_table("select.hidden", toml::Value::from(true))),
+        ]);
+
+        assert_eq!(merged, table("[select]\nhidden = true\nmode = 'replace'\nexclude = ['target']\n"));
+        assert_eq!(origins.get("select.exclude"), Some(&user));
+        assert_eq!(origins.get("select.mode"), Some(&project));
+        assert_eq!(origins.get("select.hidden"), Some(&env));
+    }
+}
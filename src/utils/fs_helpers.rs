@@ -19,4 +19,91 @@ pub fn canonicalize_path(input_path: &Path, config: &Config) -> Result<Option<Pa
     resolved_path.canonicalize()
         .map(Some)
         .map_err(|e| format!("Failed to canonicalize path {}: {}", resolved_path.display(), e))
+}
+
+/// Copies `source` to `destination`, descending into directories. Existing files
+/// below `destination` are overwritten; symbolic links are recreated on Unix and
+/// copied as the file they point to elsewhere.
+pub fn copy_recursively(source: &Path, destination: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(source)?;
+    if metadata.is_dir() {
+        std::fs::create_dir_all(destination)?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else if metadata.file_type().is_symlink() {
+        copy_symlink(source, destination)
+    } else {
+        std::fs::copy(source, destination).map(|_| ())
+    }
+}
+
+/// Removes `path`, whether it is a file, a symbolic link or a directory tree.
+pub fn remove_path(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// A hidden path next to `destination` to build a replacement in, so that
+/// the existing destination is only removed once the new one is complete.
+pub fn staging_path(destination: &Path) -> PathBuf {
+    let name = destination.file_name().unwrap_or(destination.as_os_str()).to_string_lossy();
+    destination.with_file_name(format!(".{}.ferry-{}", name, std::process::id()))
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    let target = std::fs::read_link(source)?;
+    if std::fs::symlink_metadata(destination).is_ok() {
+        std::fs::remove_file(destination)?;
+    }
+    std::os::unix::fs::symlink(target, destination)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    std::fs::copy(source, destination).map(|_| ())
+}
+
+/// Total size in bytes of `path`, including everything below it for a
+/// directory. Symbolic links count as themselves and unreadable entries as 0.
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| disk_usage(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+// Paths are converted as raw bytes on Unix so that names which are not valid
+// UTF-8 survive a round trip through the selection file or a pipe.
+#[cfg(unix)]
+pub fn path_to_bytes(path: &Path) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(path.as_os_str().as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+pub fn path_to_bytes(path: &Path) -> Option<Vec<u8>> {
+    path.to_str().map(|s| s.as_bytes().to_vec())
+}
+
+#[cfg(unix)]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
 }
\ No newline at end of file
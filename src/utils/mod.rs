@@ -1 +1,4 @@
-pub mod fs_helpers;
\ No newline at end of file
+pub mod format;
+pub mod fs_helpers;
+pub mod walker;
+pub mod terminal;
\ No newline at end of file
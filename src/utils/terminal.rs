@@ -0,0 +1,82 @@
+use std::io::{self, Stdout};
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+
+pub type TuiTerminal = Terminal<CrosstermBackend<Stdout>>;
+
+/// Whether a `TerminalGuard` has the terminal, so the panic hook only
+/// restores it while a TUI is running.
+static GUARD_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Puts the terminal into raw mode on the alternate screen with mouse capture,
+/// and puts it back when dropped, so an early return or a panic in a TUI never
+/// leaves the shell unusable.
+pub struct TerminalGuard {
+    terminal: TuiTerminal,
+    active: bool,
+}
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self, String> {
+        install_panic_hook();
+        enable_raw_mode().map_err(|e| format!("Failed to enable raw mode: {}", e))?;
+        GUARD_ACTIVE.store(true, Ordering::SeqCst);
+        let setup = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+            .and_then(|_| Terminal::new(CrosstermBackend::new(io::stdout())));
+        match setup {
+            Ok(terminal) => Ok(TerminalGuard { terminal, active: true }),
+            Err(e) => {
+                let _ = restore_terminal();
+                Err(format!("Failed to set up the terminal: {}", e))
+            }
+        }
+    }
+
+    pub fn terminal(&mut self) -> &mut TuiTerminal {
+        &mut self.terminal
+    }
+
+    /// Restores the terminal now, reporting failures that `Drop` has to ignore.
+    pub fn restore(mut self) -> Result<(), String> {
+        self.active = false;
+        restore_terminal().map_err(|e| format!("Failed to restore the terminal: {}", e))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if self.active {
+            let _ = restore_terminal();
+        }
+    }
+}
+
+fn restore_terminal() -> io::Result<()> {
+    GUARD_ACTIVE.store(false, Ordering::SeqCst);
+    // Undo every step even if one of them fails, then report the first failure.
+    let raw_mode = disable_raw_mode();
+    let screen = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, crossterm::cursor::Show);
+    raw_mode.and(screen)
+}
+
+/// Restores the terminal before the default panic message is printed, as the
+/// message would otherwise be lost on the alternate screen. Once the TUI has
+/// exited the hook leaves the terminal alone.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if GUARD_ACTIVE.load(Ordering::SeqCst) {
+                let _ = restore_terminal();
+            }
+            previous_hook(info);
+        }));
+    });
+}
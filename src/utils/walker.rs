@@ -0,0 +1,45 @@
+use std::path::Path;
+use ignore::{DirEntry, WalkBuilder};
+use crate::cli::TraversalArgs;
+
+/// Name of the ferry-specific ignore file, read with the same syntax as `.gitignore`.
+pub const FERRY_IGNORE_FILE_NAME: &str = ".ferryignore";
+
+/// Creates a directory walker for `start_path` honouring the traversal options.
+/// `--min-depth` is left to the caller, since pruning by depth would skip the
+/// entries below it too.
+///
+/// Unless `--no-ignore` is given, entries listed in `.gitignore`, `.ignore`,
+/// `.ferryignore`, `.git/info/exclude` and the global git excludes file are
+/// skipped, as is the `.git` directory itself.
+pub fn new_walker(start_path: &Path, traversal: &TraversalArgs) -> WalkBuilder {
+    let respect_ignore = !traversal.no_ignore;
+
+    let mut builder = WalkBuilder::new(start_path);
+    builder
+        .standard_filters(false)
+        .hidden(!traversal.hidden())
+        .follow_links(traversal.follow_links)
+        .same_file_system(traversal.one_file_system)
+        .max_depth(traversal.max_depth)
+        .parents(respect_ignore)
+        .ignore(respect_ignore)
+        .git_ignore(respect_ignore)
+        .git_global(respect_ignore)
+        .git_exclude(respect_ignore)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b));
+
+    if respect_ignore {
+        builder.add_custom_ignore_filename(FERRY_IGNORE_FILE_NAME);
+    }
+    builder
+}
+
+/// Whether `entry` is a `.git` directory below the walk root. These are pruned
+/// alongside ignored entries unless `--no-ignore` is given.
+pub fn is_vcs_directory(entry: &DirEntry) -> bool {
+    entry.depth() > 0
+        && entry.file_name() == ".git"
+        && entry.file_type().is_some_and(|t| t.is_dir())
+}